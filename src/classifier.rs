@@ -0,0 +1,762 @@
+/*
+ * Copyright (C) 2018 Eddie Antonio Santos <easantos@ualberta.ca>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::str;
+use std::sync::OnceLock;
+
+use error::CorpusError;
+use features::{ngrams_of, NGram, Token};
+use language::Language;
+use manifest::Corpus;
+use normalize::normalize;
+use selection::{Pruning, Selection};
+use settings::{Estimation, EventModel, Priors, Settings, Unseen};
+use smoothing::{Counts, Smoothing};
+
+/**
+ * How many times an n-gram appears in each language, in the same order as the
+ * classifier's languages.
+ */
+#[derive(Debug, Default, Clone)]
+pub struct Occurance {
+  counts: Vec<f64>,
+}
+
+/**
+ * Guesses the language of words, given n-grams counted from word lists.
+ */
+pub struct Classifier {
+  /// Every language counted so far, in the order they were first seen.
+  pub(crate) languages: Vec<Language>,
+  pub(crate) features: HashMap<NGram, Occurance>,
+  /// How many words were counted in each language.
+  pub(crate) words: Occurance,
+  /// How many times each character was counted in each language, for backing
+  /// off from unseen n-grams.
+  pub(crate) unigrams: HashMap<char, Occurance>,
+  pub(crate) settings: Settings,
+  min_confidence: f64,
+  /// Derived from the features; computed when the first word is classified
+  /// after they last changed.
+  totals: OnceLock<Totals>,
+}
+
+/**
+ * Sums over all features, precomputed so that classification is fast.
+ */
+struct Totals {
+  /// How many n-grams were counted in each language.
+  ngrams: Occurance,
+  /// How many distinct n-grams were seen in each language.
+  types: Occurance,
+  /// For absolute discounting and Kneser-Ney: how much was discounted from
+  /// the n-grams of each language.
+  discounted: Occurance,
+  /// How many characters were counted in each language.
+  chars: Occurance,
+  /// For Kneser-Ney smoothing: for each (n - 1)-gram suffix, how many known
+  /// n-grams end with it, and how many of those were seen in each language.
+  suffixes: HashMap<NGram, (u32, Occurance)>,
+  /// For the Bernoulli model (otherwise empty): the log-probability that a
+  /// word in each language has none of the known n-grams.
+  absent_log_probs: Vec<f64>,
+}
+
+/**
+ * The outcome of classifying a single word.
+ */
+#[derive(Debug, Clone)]
+pub struct Classification {
+  /// The word, after normalization.
+  pub word: String,
+  /// The language the word most likely belongs to, unless the classifier abstained.
+  pub verdict: Verdict,
+  /// How many of the word's n-grams were known to the model.
+  pub evidence: usize,
+  /// How many of the word's n-grams were never seen in training.
+  pub unseen: usize,
+  /// How each language scored, from most to least likely.
+  pub scores: Vec<Score>,
+  /// How much more probable the chosen language is than the runner-up,
+  /// from 0.0 (a coin toss) to 1.0 (certain).
+  pub confidence: f64,
+}
+
+/**
+ * Either a language, or the reason why the classifier refused to pick one.
+ */
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum Verdict {
+  Language(Language),
+  Unknown(Reason),
+}
+
+/**
+ * Why the classifier abstained.
+ */
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum Reason {
+  /// None of the word's n-grams were ever seen in training, and unseen
+  /// n-grams are skipped, or backed off to characters that were never seen
+  /// either (or the word is empty).
+  NoEvidence,
+  /// The top languages are exactly as likely as each other.
+  Tie,
+  /// The confidence fell below the classifier's threshold.
+  LowConfidence,
+}
+
+/**
+ * How well a word fits a particular language.
+ */
+#[derive(Debug, Clone)]
+pub struct Score {
+  pub language: Language,
+  /// The log-probability of the language, before looking at the word.
+  pub log_prior: f64,
+  /// The log-likelihood of the word's n-grams, assuming it's in this language.
+  pub log_likelihood: f64,
+  /// The probability that the word is in this language, given its prior and
+  /// likelihood. These sum to 1 over all languages.
+  pub posterior: f64,
+}
+
+
+impl Classifier {
+  pub fn new() -> Classifier {
+    Classifier::with_settings(Settings::default())
+  }
+
+  /**
+   * A classifier trained with the given settings, which should have been
+   * checked with Settings::validate().
+   */
+  pub fn with_settings(settings: Settings) -> Classifier {
+    Classifier {
+      languages: Vec::new(),
+      features: HashMap::new(),
+      words: Occurance::default(),
+      unigrams: HashMap::new(),
+      settings,
+      min_confidence: 0.0,
+      totals: OnceLock::new(),
+    }
+  }
+
+  pub fn settings(&self) -> &Settings {
+    &self.settings
+  }
+
+  /**
+   * Every language the classifier can choose from.
+   */
+  pub fn languages(&self) -> &[Language] {
+    &self.languages
+  }
+
+  /**
+   * Replaces the priors, e.g., when the proportion of each language in the
+   * text to classify is known to differ from the training corpora.
+   */
+  pub fn set_priors(&mut self, priors: Priors) {
+    self.settings.priors = priors;
+  }
+
+  /**
+   * Makes classify() abstain whenever its confidence is lower than the given
+   * threshold, between 0.0 and 1.0. By default, the classifier only abstains
+   * on ties.
+   *
+   * Panics if the threshold is not between 0.0 and 1.0 (or is NaN).
+   */
+  pub fn set_min_confidence(&mut self, min_confidence: f64) {
+    assert!((0.0..=1.0).contains(&min_confidence),
+            "minimum confidence must be between 0 and 1; got {}", min_confidence);
+    self.min_confidence = min_confidence;
+  }
+
+  /**
+   * Given a filename, gets a set of all of the n-grams present in each word.
+   */
+  pub fn count_ngrams_in_file(&mut self, filename: &str, lang: Language) -> Result<(), CorpusError> {
+    self.count_ngrams_in_corpus(&Corpus::new(lang, filename)).map(|_skipped| ())
+  }
+
+  /**
+   * Counts the n-grams of every word in the corpus, according to its weight
+   * (and, in a frequency list, each word's count).
+   *
+   * A lenient corpus skips lines that can't be read, which are returned;
+   * otherwise, the first bad line is an error. Either way, it's an error if
+   * there are no words at all, which also holds the skipped lines.
+   */
+  pub fn count_ngrams_in_corpus(&mut self, corpus: &Corpus) -> Result<Vec<CorpusError>, CorpusError> {
+    let path = &corpus.path;
+    let file = File::open(path).map_err(|err| CorpusError::from_io(path.clone(), err))?;
+    let mut reader = BufReader::new(file);
+
+    let mut skipped = Vec::new();
+    let mut counted_any = false;
+    let mut buffer = Vec::new();
+    let mut line_number = 0;
+    loop {
+      // Read bytes rather than lines, so that one line of invalid UTF-8
+      // doesn't prevent reading the rest.
+      buffer.clear();
+      let size = reader.read_until(b'\n', &mut buffer)
+        .map_err(|err| CorpusError::Io(path.clone(), err))?;
+      if size == 0 {
+        break;
+      }
+      line_number += 1;
+
+      let parsed = str::from_utf8(&buffer)
+        .map_err(|_| CorpusError::InvalidEncoding { path: path.clone(), line: line_number })
+        .and_then(|line| {
+          let line = line.trim_end_matches(['\n', '\r']);
+          corpus.format.parse_line(line).map_err(|message| {
+            CorpusError::InvalidLine { path: path.clone(), line: line_number, message }
+          })
+        });
+      match parsed {
+        Ok((word, weight)) => counted_any |= self.count_word(word, corpus.language, weight * corpus.weight),
+        Err(err) if corpus.lenient => skipped.push(err),
+        Err(err) => return Err(err),
+      }
+    }
+
+    if !counted_any {
+      return Err(CorpusError::EmptyCorpus { path: path.clone(), skipped });
+    }
+
+    Ok(skipped)
+  }
+
+  /**
+   * Counts each n-gram in the word as an occurance in the given language.
+   *
+   * The next word classified afterwards takes time proportional to the number
+   * of known n-grams, to sum over them again; the words after it don't.
+   */
+  pub fn count_ngrams_in_word(&mut self, word: &str, lang: Language) {
+    self.count_weighted_ngrams_in_word(word, lang, 1.0);
+  }
+
+  /**
+   * Like count_ngrams_in_word(), but the word counts as `weight` words. Words
+   * whose weight is zero, negative, or not finite are not counted at all.
+   */
+  pub fn count_weighted_ngrams_in_word(&mut self, word: &str, lang: Language, weight: f64) {
+    self.count_word(word, lang, weight);
+  }
+
+  /**
+   * Counts the word, unless it's empty or its weight isn't a positive number.
+   * Returns whether it was counted.
+   */
+  fn count_word(&mut self, word: &str, lang: Language, weight: f64) -> bool {
+    let word = normalize(word, self.settings.normalization);
+    if word.is_empty() || !(weight > 0.0 && weight.is_finite()) {
+      return false;
+    }
+
+    self.totals = OnceLock::new();
+    let index = self.index_of(lang);
+    *self.words.of_mut(index) += weight;
+    for ch in word.chars() {
+      *self.unigrams.entry(ch).or_default().of_mut(index) += weight;
+    }
+    for ngram in self.features_of(&word) {
+      let occ = self.features.entry(ngram).or_default();
+      *occ.of_mut(index) += weight;
+    }
+
+    true
+  }
+
+  /**
+   * Removes unhelpful features, and reports how many were removed.
+   */
+  pub fn prune_features(&mut self) -> Pruning {
+    let before = self.features.len();
+
+    // What feature selection compares each n-gram against: the number of
+    // words that could have had it, or in the multinomial model, where an
+    // n-gram can be counted many times per word, the number of n-grams.
+    let events = match self.settings.event_model {
+      EventModel::Multinomial => self.features.values().fold(Occurance::default(), |mut sum, occ| {
+        sum.add(occ);
+        sum
+      }),
+      EventModel::Presence | EventModel::Bernoulli => self.words.clone(),
+    };
+
+    // "Unhelpful" features are n-grams that have rarely been witnessed.
+    // Remove them, since they don't add much when classifying.
+    let min_count = self.settings.min_count;
+    self.features.retain(|_ngram, occ| occ.total() >= min_count);
+    let below_min_count = before - self.features.len();
+
+    // Then, keep only the n-grams that best tell the languages apart.
+    let mut not_selected = 0;
+    if let Some(Selection { criterion, keep }) = self.settings.selection {
+      if self.features.len() > keep {
+        let mut scored: Vec<_> = self.features.iter()
+          .map(|(ngram, occ)| (criterion.score(occ, &events, self.languages.len()), ngram.clone()))
+          .collect();
+        // Best first; break ties by the n-gram itself, so that the same n-grams
+        // are kept every time.
+        scored.sort_by(|a, b| {
+          b.0.partial_cmp(&a.0).unwrap_or(Ordering::Equal).then_with(|| a.1.cmp(&b.1))
+        });
+        for (_score, ngram) in scored.drain(keep..) {
+          self.features.remove(&ngram);
+        }
+        not_selected = before - below_min_count - self.features.len();
+      }
+    }
+
+    self.totals = OnceLock::new();
+    Pruning { before, below_min_count, not_selected }
+  }
+
+  /**
+   * Normalizes the word, then guesses which language it's from.
+   */
+  pub fn classify(&self, word: &str) -> Classification {
+    let word = normalize(word, self.settings.normalization);
+    let ngrams = self.features_of(&word);
+    let evidence = ngrams.iter()
+      .filter(|ngram| self.features.contains_key(ngram))
+      .count();
+    let unseen = ngrams.len() - evidence;
+
+    let totals = self.totals.get_or_init(|| self.compute_totals());
+
+    let mut scores: Vec<Score> = self.languages.iter().enumerate().map(|(index, &language)| {
+      let log_likelihood = match self.settings.event_model {
+        EventModel::Presence | EventModel::Multinomial => ngrams.iter()
+          .filter_map(|ngram| {
+            self.log_prob(ngram, index, totals)
+              .or_else(|| self.unseen_log_prob(ngram, index, totals))
+          })
+          .fold(0.0, |sum, log_prob| sum + log_prob),
+        EventModel::Bernoulli => self.bernoulli_log_likelihood(&ngrams, index, totals),
+      };
+      Score { language, log_prior: self.log_prior(index), log_likelihood, posterior: 0.0 }
+    }).collect();
+
+    let confidence = rank(&mut scores);
+    let no_evidence = ngrams.is_empty() || (evidence == 0 && !self.knows_any_char(&word));
+    let verdict = self.decide(&scores, confidence, no_evidence);
+
+    Classification { word, verdict, evidence, unseen, scores, confidence }
+  }
+
+  /**
+   * Picks the most likely language from the ranked scores, unless there's no
+   * evidence, or it's not confident enough.
+   */
+  pub(crate) fn decide(&self, scores: &[Score], confidence: f64, no_evidence: bool) -> Verdict {
+    if scores.is_empty() || no_evidence {
+      Verdict::Unknown(Reason::NoEvidence)
+    } else if confidence <= 0.0 {
+      Verdict::Unknown(Reason::Tie)
+    } else if confidence < self.min_confidence {
+      Verdict::Unknown(Reason::LowConfidence)
+    } else {
+      Verdict::Language(scores[0].language)
+    }
+  }
+
+  /**
+   * Whether anything but the word's n-grams can tell the languages apart when
+   * none of its n-grams are known: backing off only helps if the model has
+   * seen at least one of its characters, while penalties always apply.
+   */
+  fn knows_any_char(&self, word: &str) -> bool {
+    match self.settings.unseen {
+      Unseen::Skip => false,
+      Unseen::Backoff => word.chars().any(|ch| self.unigrams.contains_key(&ch)),
+      Unseen::Penalty(_) => true,
+    }
+  }
+
+  /**
+   * The index of the language in every Occurance, adding it if it's new.
+   */
+  fn index_of(&mut self, language: Language) -> usize {
+    match self.languages.iter().position(|&l| l == language) {
+      Some(index) => index,
+      None => {
+        self.languages.push(language);
+        self.languages.len() - 1
+      },
+    }
+  }
+
+  /**
+   * The index of a language that the classifier knows.
+   */
+  pub(crate) fn position_of(&self, language: Language) -> usize {
+    self.languages.iter().position(|&l| l == language).expect("known language")
+  }
+
+  /**
+   * The log-probability of the (index-th) language, before looking at the word.
+   */
+  pub(crate) fn log_prior(&self, index: usize) -> f64 {
+    let num_languages = self.languages.len() as f64;
+    let (weight, total) = match self.settings.priors {
+      Priors::Uniform => (1.0, num_languages),
+      Priors::Corpus if self.words.total() <= 0.0 => (1.0, num_languages),
+      Priors::Corpus => (self.words.of(index), self.words.total()),
+      Priors::Explicit(ref weights) => (
+        weights.get(&self.languages[index]).cloned().unwrap_or(0.0),
+        self.languages.iter().filter_map(|l| weights.get(l)).sum(),
+      ),
+    };
+    if total <= 0.0 {
+      return -num_languages.ln();
+    }
+
+    weight.ln() - total.ln()
+  }
+
+  /**
+   * Extracts n-grams from a normalized word: each distinct n-gram once in the
+   * presence and Bernoulli models, or every occurance of each n-gram in the
+   * multinomial model.
+   */
+  fn features_of(&self, word: &str) -> Vec<NGram> {
+    let mut ngrams = ngrams_of(word, self.settings.order);
+    match self.settings.event_model {
+      // Sorted, so that log-probabilities are always added in the same order.
+      EventModel::Presence | EventModel::Bernoulli => {
+        ngrams.sort();
+        ngrams.dedup();
+      },
+      EventModel::Multinomial => (),
+    }
+    ngrams
+  }
+
+  /**
+   * The log-probability that a word in the given language has exactly the
+   * given n-grams, and none of the other known n-grams.
+   *
+   * Rather than visit every known n-gram, start with the (precomputed)
+   * log-probability that all of them are absent, and then correct it for
+   * each n-gram that is actually present.
+   */
+  fn bernoulli_log_likelihood(&self, ngrams: &[NGram], index: usize, totals: &Totals) -> f64 {
+    let all_absent = totals.absent_log_probs[index];
+
+    ngrams.iter().fold(all_absent, |sum, ngram| {
+      match self.features.get(ngram) {
+        Some(occ) => {
+          let p = self.presence_prob(occ, index);
+          sum + p.ln() - (1.0 - p).ln()
+        },
+        None => sum + self.unseen_log_prob(ngram, index, totals).unwrap_or(0.0),
+      }
+    })
+  }
+
+  /**
+   * What an unseen n-gram contributes to the log-likelihood, if anything.
+   */
+  fn unseen_log_prob(&self, ngram: &NGram, index: usize, totals: &Totals) -> Option<f64> {
+    match self.settings.unseen {
+      Unseen::Skip => None,
+      Unseen::Backoff => Some(self.backoff_log_prob(ngram, index, totals)),
+      Unseen::Penalty(ref penalties) =>
+        Some(penalties.get(&self.languages[index]).cloned().unwrap_or(0.0)),
+    }
+  }
+
+  /**
+   * The log-probability of each character in the n-gram, as if they were
+   * independent. Characters never seen in any language share one extra count.
+   */
+  fn backoff_log_prob(&self, ngram: &NGram, index: usize, totals: &Totals) -> f64 {
+    let denominator = totals.chars.of(index) + self.unigrams.len() as f64 + 1.0;
+
+    ngram.0.iter()
+      .filter_map(|token| match *token {
+        Token::Char(ch) => Some(ch),
+        _ => None,
+      })
+      .map(|ch| {
+        let count = self.unigrams.get(&ch).map_or(0.0, |occ| occ.of(index));
+        (count + 1.0).ln() - denominator.ln()
+      })
+      .sum()
+  }
+
+  /**
+   * The log-probability that a word in the given language has none of the
+   * known n-grams.
+   */
+  fn all_absent_log_prob(&self, index: usize) -> f64 {
+    self.features.values()
+      .map(|occ| (1.0 - self.presence_prob(occ, index)).ln())
+      .sum()
+  }
+
+  /**
+   * The probability that a word in the given language has this n-gram.
+   */
+  fn presence_prob(&self, occ: &Occurance, index: usize) -> f64 {
+    self.settings.smoothing.binary_probability(occ.of(index), self.words.of(index))
+  }
+
+  fn compute_totals(&self) -> Totals {
+    let mut ngrams = Occurance::default();
+    let mut types = Occurance::default();
+    let mut discounted = Occurance::default();
+    let mut suffixes: HashMap<NGram, (u32, Occurance)> = HashMap::new();
+    let kneser_ney = matches!(self.settings.smoothing, Smoothing::KneserNey { .. });
+
+    for (ngram, occ) in &self.features {
+      let seen = occ.seen();
+      ngrams.add(occ);
+      types.add(&seen);
+      for index in 0..self.languages.len() {
+        *discounted.of_mut(index) += self.settings.smoothing.discount_of(occ.of(index));
+      }
+
+      if kneser_ney {
+        let suffix = suffixes.entry(ngram.suffix()).or_default();
+        suffix.0 += 1;
+        suffix.1.add(&seen);
+      }
+    }
+
+    let mut chars = Occurance::default();
+    for occ in self.unigrams.values() {
+      chars.add(occ);
+    }
+
+    let absent_log_probs = match self.settings.event_model {
+      EventModel::Bernoulli => (0..self.languages.len())
+        .map(|index| self.all_absent_log_prob(index))
+        .collect(),
+      _ => Vec::new(),
+    };
+
+    Totals { ngrams, types, discounted, chars, suffixes, absent_log_probs }
+  }
+
+  /**
+   * The log-probability of seeing the n-gram in a word of the given language
+   * (or, with legacy estimation, of the language, given the n-gram).
+   */
+  fn log_prob(&self, ngram: &NGram, index: usize, totals: &Totals) -> Option<f64> {
+    let occurance = self.features.get(ngram)?;
+    let smoothing = self.settings.smoothing;
+    let count = occurance.of(index);
+
+    let prob = match (self.settings.estimation, self.settings.event_model) {
+      (Estimation::Legacy, _) =>
+        smoothing.legacy_probability(count, occurance.total(), self.num_features()),
+      (Estimation::Likelihood, EventModel::Multinomial) => smoothing.probability(Counts {
+        count,
+        total: totals.ngrams.of(index),
+        types: totals.types.of(index),
+        vocabulary: self.num_features(),
+        continuation: self.continuation_prob(ngram, index, totals),
+        discounted: totals.discounted.of(index),
+      }),
+      (Estimation::Likelihood, _) => self.presence_prob(occurance, index),
+    };
+
+    Some(prob.ln())
+  }
+
+  /**
+   * For Kneser-Ney smoothing: the probability of the n-gram according to how
+   * many distinct n-grams in the language share its suffix. Each suffix gets
+   * an extra (add-one) count so that no n-gram is impossible.
+   */
+  fn continuation_prob(&self, ngram: &NGram, index: usize, totals: &Totals) -> f64 {
+    if totals.suffixes.is_empty() {
+      return 0.0;
+    }
+
+    let (group_size, seen) = match totals.suffixes.get(&ngram.suffix()) {
+      Some(&(group_size, ref seen)) => (group_size, seen.of(index)),
+      None => return 0.0,
+    };
+
+    let num_suffixes = totals.suffixes.len() as f64;
+    let suffix_prob = (seen + 1.0) / (totals.types.of(index) + num_suffixes);
+    suffix_prob / f64::from(group_size)
+  }
+
+  fn num_features(&self) -> f64 {
+    self.features.len() as f64
+  }
+}
+
+
+impl Classification {
+  /**
+   * The chosen language, if any.
+   */
+  pub fn language(&self) -> Option<Language> {
+    match self.verdict {
+      Verdict::Language(language) => Some(language),
+      Verdict::Unknown(_) => None,
+    }
+  }
+
+  /**
+   * The probability that the most likely language is correct.
+   */
+  pub fn posterior(&self) -> f64 {
+    self.scores.first().map_or(0.0, |best| best.posterior)
+  }
+}
+
+
+impl fmt::Display for Reason {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    f.write_str(match *self {
+      Reason::NoEvidence => "no evidence",
+      Reason::Tie => "tie",
+      Reason::LowConfidence => "low confidence",
+    })
+  }
+}
+
+
+impl Default for Classifier {
+  fn default() -> Classifier {
+    Classifier::new()
+  }
+}
+
+
+impl Occurance {
+  pub(crate) fn from_counts(counts: Vec<f64>) -> Occurance {
+    Occurance { counts }
+  }
+
+  pub(crate) fn total(&self) -> f64 {
+    self.counts.iter().sum()
+  }
+
+  /**
+   * The count for the index-th language of the classifier.
+   */
+  pub(crate) fn of(&self, index: usize) -> f64 {
+    self.counts.get(index).cloned().unwrap_or(0.0)
+  }
+
+  fn of_mut(&mut self, index: usize) -> &mut f64 {
+    if index >= self.counts.len() {
+      self.counts.resize(index + 1, 0.0);
+    }
+    &mut self.counts[index]
+  }
+
+  fn add(&mut self, other: &Occurance) {
+    for (index, &count) in other.counts.iter().enumerate() {
+      *self.of_mut(index) += count;
+    }
+  }
+
+  /**
+   * 1 for every language with a non-zero count, otherwise 0.
+   */
+  fn seen(&self) -> Occurance {
+    Occurance { counts: self.counts.iter().map(|&count| if count > 0.0 { 1.0 } else { 0.0 }).collect() }
+  }
+}
+
+
+/**
+ * Fills in the posterior of each score, sorts them from most to least likely,
+ * and returns the confidence: the margin between the top two.
+ */
+pub(crate) fn rank(scores: &mut [Score]) -> f64 {
+  let marginal = log_sum_exp(scores.iter().map(|score| score.log_prior + score.log_likelihood));
+  for score in scores.iter_mut() {
+    score.posterior = (score.log_prior + score.log_likelihood - marginal).exp();
+  }
+
+  // Most likely first.
+  scores.sort_by(|a, b| b.posterior.total_cmp(&a.posterior));
+
+  match (scores.first(), scores.get(1)) {
+    (Some(best), Some(runner_up)) => best.posterior - runner_up.posterior,
+    (Some(best), None) => best.posterior,
+    (None, _) => 0.0,
+  }
+}
+
+/**
+ * Computes ln(exp(x1) + exp(x2) + ...) without underflowing.
+ */
+fn log_sum_exp<I: Iterator<Item = f64> + Clone>(xs: I) -> f64 {
+  let max = xs.clone().fold(f64::NEG_INFINITY, f64::max);
+  if max == f64::NEG_INFINITY {
+    return max;
+  }
+
+  max + xs.map(|x| (x - max).exp()).sum::<f64>().ln()
+}
+
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn bernoulli_shortcut_matches_every_feature() {
+    let settings = Settings { event_model: EventModel::Bernoulli, unseen: Unseen::Skip, ..Settings::default() };
+    let mut classifier = Classifier::with_settings(settings);
+    for word in &["maskwa", "nipiy", "awâsis", "wâpos"] {
+      classifier.count_ngrams_in_word(word, Language::CRK);
+    }
+    for word in &["the", "bear", "water", "child", "wasp"] {
+      classifier.count_ngrams_in_word(word, Language::ENG);
+    }
+
+    for word in &["maskwa", "the", "wapiti", "xyz"] {
+      let present = classifier.features_of(&normalize(word, classifier.settings.normalization));
+      for score in classifier.classify(word).scores {
+        let index = classifier.position_of(score.language);
+        // Visit every known n-gram, present in the word or not.
+        let brute_force: f64 = classifier.features.iter()
+          .map(|(ngram, occ)| {
+            let p = classifier.presence_prob(occ, index);
+            if present.contains(ngram) { p.ln() } else { (1.0 - p).ln() }
+          })
+          .sum();
+        assert!((score.log_likelihood - brute_force).abs() < 1e-9,
+                "{} in {}: {} instead of {}", word, score.language, score.log_likelihood, brute_force);
+      }
+    }
+  }
+}
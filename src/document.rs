@@ -0,0 +1,139 @@
+/*
+ * Copyright (C) 2018 Eddie Antonio Santos <easantos@ualberta.ca>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+
+//! Identifying the language of whole documents.
+
+use std::io::{self, BufRead};
+
+use classifier::{rank, Classifier, Reason, Score, Verdict};
+use language::Language;
+use tokenize::words;
+
+/**
+ * The outcome of classifying a whole document.
+ */
+#[derive(Debug, Clone)]
+pub struct DocumentClassification {
+  /// The language the document is most likely in, unless the classifier abstained.
+  pub verdict: Verdict,
+  /// How many words were read.
+  pub words: usize,
+  /// How many words were classified as each language, in the same order as
+  /// the classifier's languages.
+  pub counts: Vec<(Language, usize)>,
+  /// How many words the classifier abstained on.
+  pub unknown: usize,
+  /// How each language scored for the document as a whole, from most to least
+  /// likely; each log-likelihood is the sum over all of the words that had
+  /// any evidence.
+  pub scores: Vec<Score>,
+  /// How much more probable the chosen language is than the runner-up.
+  pub confidence: f64,
+}
+
+
+impl Classifier {
+  /**
+   * Classifies every word of running text, line by line, and then the text as
+   * a whole, as if it were one long word: the log-likelihoods of its words
+   * are added up (except for words with no evidence), and the document's
+   * prior is counted only once.
+   *
+   * Only one line is held in memory at a time, so documents can be as large
+   * as you like.
+   */
+  pub fn classify_document<R: BufRead>(&self, reader: R) -> io::Result<DocumentClassification> {
+    let num_languages = self.languages.len();
+    let mut log_likelihoods = vec![0.0; num_languages];
+    let mut counts = vec![0; num_languages];
+    let mut num_words = 0;
+    let mut unknown = 0;
+    let mut any_evidence = false;
+
+    for line in reader.lines() {
+      let line = line?;
+      for word in words(&line) {
+        let result = self.classify(word);
+        num_words += 1;
+
+        match result.verdict {
+          Verdict::Language(language) => counts[self.position_of(language)] += 1,
+          Verdict::Unknown(_) => unknown += 1,
+        }
+        // A word with no evidence can still score differently in each
+        // language (e.g., backing off to characters seen nowhere), which
+        // would only favour the languages with less training data.
+        if result.verdict == Verdict::Unknown(Reason::NoEvidence) {
+          continue;
+        }
+        any_evidence = true;
+        for score in &result.scores {
+          log_likelihoods[self.position_of(score.language)] += score.log_likelihood;
+        }
+      }
+    }
+
+    let mut scores: Vec<Score> = self.languages.iter().enumerate()
+      .map(|(index, &language)| Score {
+        language,
+        log_prior: self.log_prior(index),
+        log_likelihood: log_likelihoods[index],
+        posterior: 0.0,
+      })
+      .collect();
+    let confidence = rank(&mut scores);
+    let verdict = self.decide(&scores, confidence, !any_evidence);
+
+    Ok(DocumentClassification {
+      verdict,
+      words: num_words,
+      counts: self.languages.iter().cloned().zip(counts).collect(),
+      unknown,
+      scores,
+      confidence,
+    })
+  }
+}
+
+
+impl DocumentClassification {
+  /**
+   * The chosen language, if any.
+   */
+  pub fn language(&self) -> Option<Language> {
+    match self.verdict {
+      Verdict::Language(language) => Some(language),
+      Verdict::Unknown(_) => None,
+    }
+  }
+
+  /**
+   * The proportion of the document's words classified as the given language,
+   * from 0.0 to 1.0.
+   */
+  pub fn proportion(&self, language: Language) -> f64 {
+    let count = self.counts.iter()
+      .find(|&&(l, _)| l == language)
+      .map_or(0, |&(_, count)| count);
+    if self.words == 0 {
+      return 0.0;
+    }
+
+    count as f64 / self.words as f64
+  }
+}
@@ -0,0 +1,87 @@
+/*
+ * Copyright (C) 2018 Eddie Antonio Santos <easantos@ualberta.ca>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+
+//! Errors that can happen while reading a corpus.
+
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+/**
+ * Why a corpus (or a line of it) could not be read.
+ */
+#[derive(Debug)]
+pub enum CorpusError {
+  /// The file does not exist.
+  MissingFile(PathBuf),
+  /// The file could not be read.
+  Io(PathBuf, io::Error),
+  /// The (1-based) line is not valid UTF-8.
+  InvalidEncoding { path: PathBuf, line: usize },
+  /// The line is not written in the corpus's format, e.g., a frequency list
+  /// line without a count.
+  InvalidLine { path: PathBuf, line: usize, message: String },
+  /// Not a single word was counted from the file. In a lenient corpus, these
+  /// are the lines that were skipped (perhaps all of them).
+  EmptyCorpus { path: PathBuf, skipped: Vec<CorpusError> },
+}
+
+
+impl CorpusError {
+  /**
+   * Whether this is about a single line, which a lenient reader can skip.
+   */
+  pub fn is_line_error(&self) -> bool {
+    matches!(*self, CorpusError::InvalidEncoding { .. } | CorpusError::InvalidLine { .. })
+  }
+
+  pub(crate) fn from_io(path: PathBuf, err: io::Error) -> CorpusError {
+    match err.kind() {
+      io::ErrorKind::NotFound => CorpusError::MissingFile(path),
+      _ => CorpusError::Io(path, err),
+    }
+  }
+}
+
+
+impl fmt::Display for CorpusError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      CorpusError::MissingFile(ref path) => write!(f, "{}: no such file", path.display()),
+      CorpusError::Io(ref path, ref err) => write!(f, "{}: {}", path.display(), err),
+      CorpusError::InvalidEncoding { ref path, line } =>
+        write!(f, "{}:{}: not valid UTF-8", path.display(), line),
+      CorpusError::InvalidLine { ref path, line, ref message } =>
+        write!(f, "{}:{}: {}", path.display(), line, message),
+      CorpusError::EmptyCorpus { ref path, ref skipped } if !skipped.is_empty() =>
+        write!(f, "{}: no words to count, after skipping {} lines", path.display(), skipped.len()),
+      CorpusError::EmptyCorpus { ref path, .. } => write!(f, "{}: no words to count", path.display()),
+    }
+  }
+}
+
+
+impl Error for CorpusError {
+  fn source(&self) -> Option<&(dyn Error + 'static)> {
+    match *self {
+      CorpusError::Io(_, ref err) => Some(err),
+      _ => None,
+    }
+  }
+}
@@ -0,0 +1,93 @@
+/*
+ * Copyright (C) 2018 Eddie Antonio Santos <easantos@ualberta.ca>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::fmt;
+use std::iter;
+
+/**
+ * Since we're interested in counting what are common starts of words, and common ends of words, a
+ * "token" is more than simply a character---we encode the start and end of words explicitly.
+ */
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Copy, Clone)]
+pub enum Token {
+  Start,
+  End,
+  Char(char)
+}
+
+/**
+ * An n-gram is n tokens stuck together. A digraph is an n-gram of order 2.
+ */
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Clone)]
+pub struct NGram(pub Vec<Token>);
+
+
+impl NGram {
+  /**
+   * The n-gram without its first token.
+   */
+  pub fn suffix(&self) -> NGram {
+    NGram(self.0[1..].to_vec())
+  }
+}
+
+
+/**
+ * Lists every n-gram in a word, in order, including repeats. Assumes the word
+ * has already been preprocessed.
+ *
+ * The word is padded with (order - 1) Start and End tokens, so that even
+ * single-character words have at least one n-gram, and so that n-grams of
+ * order 2 are the same digraphs as always.
+ */
+pub fn ngrams_of(text: &str, order: usize) -> Vec<NGram> {
+  if text.is_empty() {
+    return Vec::new();
+  }
+  assert!(!text.ends_with('\n'));
+  assert!(order >= 1, "n-grams must have at least one token");
+
+  let padding = order - 1;
+  let tokens: Vec<Token> = iter::repeat_n(Token::Start, padding)
+    .chain(text.chars().map(Token::Char))
+    .chain(iter::repeat_n(Token::End, padding))
+    .collect();
+
+  tokens.windows(order)
+    .map(|window| NGram(window.to_vec()))
+    .collect()
+}
+
+
+/**
+ * Displays a character. Note that this will panic if the characters are either
+ * '^' or '$' as those are used to indicate the Start and End tokens, respectively.
+ */
+impl fmt::Display for Token {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{}", match *self {
+      Token::Start => '^',
+      Token::End => '$',
+      Token::Char(c) => {
+        /* Make sure the meaning of '^' and '$' is unambiguous---
+         * we wouldn't want our shorthand to be an actual character! */
+        assert!(c != '^' || c != '$');
+        c
+      },
+    })
+  }
+}
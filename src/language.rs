@@ -0,0 +1,88 @@
+/*
+ * Copyright (C) 2018 Eddie Antonio Santos <easantos@ualberta.ca>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+
+use std::fmt;
+use std::str;
+use std::str::FromStr;
+
+/// The longest label a language can have, in bytes.
+const MAX_LABEL: usize = 8;
+
+/**
+ * Which language? A short label, usually an ISO 639-3 code such as "crk"
+ * (nêhiyawêwin/Plains Cree), "eng" (English), "oji" (Ojibwe), or "crg"
+ * (Michif), although any label of up to eight letters, digits, hyphens or
+ * underscores will do (e.g., "crk-Latn").
+ */
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Copy, Clone)]
+pub struct Language {
+  /// The label, padded with NUL bytes.
+  label: [u8; MAX_LABEL],
+}
+
+
+impl Language {
+  /// nêhiyawêwin/Plains Cree.
+  pub const CRK: Language = Language { label: *b"crk\0\0\0\0\0" };
+  /// English.
+  pub const ENG: Language = Language { label: *b"eng\0\0\0\0\0" };
+
+  /**
+   * The language's label, e.g., "crk".
+   */
+  pub fn as_str(&self) -> &str {
+    let len = self.label.iter().position(|&b| b == 0).unwrap_or(MAX_LABEL);
+    str::from_utf8(&self.label[..len]).expect("label is ASCII")
+  }
+}
+
+
+/**
+ * Parses a language label, such as an ISO 639-3 code.
+ */
+impl FromStr for Language {
+  type Err = ();
+
+  fn from_str(s: &str) -> Result<Language, ()> {
+    let valid = |b: u8| b.is_ascii_alphanumeric() || b == b'-' || b == b'_';
+    if s.is_empty() || s.len() > MAX_LABEL || !s.bytes().all(valid) {
+      return Err(());
+    }
+
+    let mut label = [0; MAX_LABEL];
+    label[..s.len()].copy_from_slice(s.as_bytes());
+    Ok(Language { label })
+  }
+}
+
+
+/**
+ * Displays the language's label.
+ */
+impl fmt::Display for Language {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    f.write_str(self.as_str())
+  }
+}
+
+
+impl fmt::Debug for Language {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "Language({:?})", self.as_str())
+  }
+}
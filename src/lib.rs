@@ -0,0 +1,57 @@
+/*
+ * Copyright (C) 2018 Eddie Antonio Santos <easantos@ualberta.ca>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Guesses whether a word is in nêhiyawêwin (Plains Cree) or English---or any
+//! other languages that share a Latin orthography, given word lists for each.
+//!
+//! A [`Classifier`] is trained by counting the n-grams---by default, digraphs,
+//! or pairs of adjacent characters, including the start and end of the
+//! word---found in word lists of each language. Words are then classified
+//! using naïve Bayes.
+//!
+//! [`Classifier`]: struct.Classifier.html
+
+extern crate caseless;
+extern crate unicode_normalization;
+
+mod classifier;
+mod document;
+mod error;
+mod features;
+mod language;
+mod manifest;
+mod model;
+mod normalize;
+mod selection;
+mod settings;
+mod smoothing;
+mod syllabics;
+mod tagging;
+mod tokenize;
+
+pub use classifier::{Classification, Classifier, Reason, Score, Verdict};
+pub use document::DocumentClassification;
+pub use error::CorpusError;
+pub use language::Language;
+pub use manifest::{Corpus, Damping, Format, Manifest};
+pub use normalize::{normalize, Normalization};
+pub use selection::{Criterion, Pruning, Selection};
+pub use settings::{Estimation, EventModel, Priors, Settings, Unseen};
+pub use smoothing::Smoothing;
+pub use syllabics::transliterate;
+pub use tagging::{Span, DEFAULT_SWITCH_PENALTY};
+pub use tokenize::{words, Words};
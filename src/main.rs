@@ -15,227 +15,639 @@
  * along with this program.  If not, see <http://www.gnu.org/licenses/>.
  */
 
-use std::collections::{HashMap, HashSet};
-use std::fmt;
+extern crate crk_or_eng;
+
+use std::env;
 use std::fs::File;
-use std::io::{self, BufRead, BufReader};
+use std::io::{self, BufRead, BufReader, BufWriter};
+use std::process;
+use std::str::FromStr;
+
+use crk_or_eng::{transliterate, words, Classifier, Corpus, CorpusError, Format, Language, Manifest, Priors, Pruning, Settings, Verdict};
+use crk_or_eng::DEFAULT_SWITCH_PENALTY;
+
+const USAGE: &str = "\
+Usage:
+  crk-or-eng train [TRAINING-OPTIONS] [-o MODEL] LANG=CORPUS...
+  crk-or-eng train --manifest MANIFEST [-o MODEL]
+  crk-or-eng classify [OPTIONS] (--model MODEL | [TRAINING-OPTIONS] LANG=CORPUS...)
+  crk-or-eng tag [OPTIONS] (--model MODEL | [TRAINING-OPTIONS] LANG=CORPUS...)
+  crk-or-eng eval [OPTIONS] (--model MODEL | [TRAINING-OPTIONS] LANG=CORPUS...)
+                  --test LANG=FILE...
+  crk-or-eng transliterate
+  crk-or-eng help
+
+Subcommands:
+  train     Count n-grams in the given corpora and save the model.
+  classify  Classify each word read from stdin, one per line (or with --text,
+            every word of running text; with --document, stdin as a whole).
+  tag       Split each line read from stdin into words, and label spans of
+            words in the same language, e.g., \"[crk tânisi] [eng my friend]\".
+  eval      Report accuracy on held-out test files.
+  transliterate
+            Rewrite the Cree syllabics read from stdin in SRO, e.g., \"ᑖᓂᓯ\"
+            as \"tânisi\". Syllabics are transliterated like this before
+            training and classifying, too.
+
+Options:
+  -o, --output MODEL  Where to save the model (default: stdout).
+  --manifest FILE     Train on the corpora and settings listed in FILE, instead
+                      of LANG=CORPUS arguments and training options.
+  --model MODEL       Use a model saved by the train subcommand.
+  --test LANG=FILE    A held-out file of words in the given language.
+  -v, --verbose       Print each word's scores to stderr.
+  --text              Split each line read by classify into words, ignoring
+                      punctuation and numbers, instead of taking it as one word.
+  --document          Classify all of stdin as one document, and report the
+                      proportion of its words in each language.
+  --switch-penalty P  How much less likely tag thinks it is for the language to
+                      change between two words, as a log-probability (default:
+                      3). 0 tags each word on its own.
+  --min-confidence P  Answer \"Unknown\" when the margin between the two most
+                      likely languages is below P, between 0 and 1 (default: 0).
+  --priors PRIORS     How likely each language is before looking at the word:
+                      \"corpus\" (in proportion to the words in each corpus, the
+                      default when training), \"uniform\", or relative weights
+                      such as \"eng=0.8,crk=0.2\". Overrides a saved model's priors.
+
+Training options:
+  --order N           Count n-grams of N characters (default: 2, digraphs).
+  --event-model M     Count each n-gram once per word (\"presence\", the default)
+                      or every time it appears (\"multinomial\"). \"bernoulli\"
+                      also counts n-grams absent from the word as evidence.
+  --estimation E      Estimate P(n-gram|language) (\"likelihood\", the default) or
+                      P(language|n-gram) like older versions did (\"legacy\").
+  --smoothing S       \"lidstone:ALPHA\", \"laplace\" (the default, same as
+                      \"lidstone:1\"), \"witten-bell\", \"absolute:DISCOUNT\", or
+                      \"kneser-ney:DISCOUNT\". All but Lidstone require
+                      --event-model multinomial.
+  --min-count N       Drop n-grams seen fewer than N times (default: 2). Each
+                      time counts as much as the word it's in: in a frequency
+                      list, the word's (damped) count.
+  --select C:K        Then keep only the K n-grams that score highest by the
+                      criterion C: \"chi-square\", \"mutual-information\", or
+                      \"log-odds\"; for example, \"chi-square:1000\".
+  --format F          How each CORPUS is written: \"words\", one per line (the
+                      default), or \"counts\", a word, a tab, and its count per
+                      line. \"counts:log\" and \"counts:sqrt\" dampen the counts.
+                      A corpus given as LANG:F=CORPUS is written in its own
+                      format F instead, e.g., crk:counts:log=frequencies.tsv.
+  --lenient           Skip (and report) lines of each CORPUS that can't be read,
+                      instead of giving up.
+  --unseen POLICY     What to do with n-grams never seen in training: \"backoff\"
+                      to the probabilities of their characters (the default),
+                      \"skip\" them, or add a log-probability penalty for each
+                      language, such as \"penalty:crk=-8,eng=-4\".
+  --keep-vowel-length Tell long vowels (â, ê, î, ô, or ā, ē, ī, ō) from short
+                      ones, instead of stripping all diacritics.
+  --case-fold         Fold case fully instead of lowercasing, so that, e.g.,
+                      \"ß\" and \"ss\" are the same.
+
+Each CORPUS is a file with one word per line, labelled with its language: a
+code of up to eight letters, digits, hyphens or underscores, such as an ISO
+639-3 code; for example: crk=itwêwina eng=words oji=ojibwe.txt
+Instead of a saved model, classify and eval can train on corpora directly.
+";
 
 /**
- * Since we're interested in counting what are common starts of words, and common ends of words, a
- * "token" is more than simply a character---we encode the start and end of words explicitly.
+ * Things that can go wrong when running a subcommand.
  */
-#[derive(PartialEq, Eq, Hash, Debug, Copy, Clone)]
-enum Token {
-  Start,
-  End,
-  Char(char)
+#[derive(Debug)]
+enum CliError {
+  /// The arguments did not make sense; the usage message should be shown.
+  Usage(String),
+  /// A file could not be read.
+  Io(String, io::Error),
+  /// A corpus could not be read.
+  Corpus(CorpusError),
 }
 
 /**
- * A digraph is two tokens stuck together.
+ * How to read each of the LANG=CORPUS arguments.
  */
-#[derive(PartialEq, Eq, Hash, Debug, Copy, Clone)]
-struct Digraph(Token, Token);
+#[derive(Default)]
+struct Reading {
+  format: Format,
+  lenient: bool,
+}
+
+
+
+impl Reading {
+  /**
+   * Reads each corpus as the options say, unless it was given its own format.
+   */
+  fn apply_to(&self, labelled: Vec<(Corpus, Option<Format>)>) -> Vec<Corpus> {
+    labelled.into_iter().map(|(mut corpus, format)| {
+      corpus.format = format.unwrap_or(self.format);
+      corpus.lenient = self.lenient;
+      corpus
+    }).collect()
+  }
+}
+
+
+fn main() {
+  let args: Vec<String> = env::args().skip(1).collect();
+
+  match run(&args) {
+    Ok(()) => (),
+    Err(CliError::Usage(message)) => {
+      eprintln!("crk-or-eng: {}\n\n{}", message, USAGE);
+      process::exit(2);
+    },
+    Err(CliError::Io(path, err)) => {
+      eprintln!("crk-or-eng: {}: {}", path, err);
+      process::exit(1);
+    },
+    Err(CliError::Corpus(err)) => {
+      eprintln!("crk-or-eng: {}", err);
+      process::exit(1);
+    },
+  }
+}
 
 /**
- * Which language?
+ * Dispatches to the appropriate subcommand.
  */
-#[derive(Debug)]
-enum Language {
-  Crk, // nêhiyawêwin/Plains Cree
-  Eng, // English
+fn run(args: &[String]) -> Result<(), CliError> {
+  let (subcommand, rest) = match args.split_first() {
+    Some((subcommand, rest)) => (subcommand.as_str(), rest),
+    None => return Err(CliError::Usage("no subcommand given".into())),
+  };
+
+  match subcommand {
+    "train" => train(rest),
+    "classify" => classify(rest),
+    "tag" => tag(rest),
+    "eval" => eval(rest),
+    "transliterate" => transliterate_stdin(rest),
+    "help" | "-h" | "--help" => {
+      print!("{}", USAGE);
+      Ok(())
+    },
+    _ => Err(CliError::Usage(format!("unknown subcommand '{}'", subcommand))),
+  }
 }
 
 /**
- * How many times a digraph appears in nêhiyawêwin vs. English.
+ * Trains a model on the given corpora and saves it.
  */
-#[derive(Debug)]
-struct Occurance {
-  crk: u32,
-  eng: u32
-}
+fn train(args: &[String]) -> Result<(), CliError> {
+  let mut settings = Settings::default();
+  let mut reading = Reading::default();
+  let mut output = None;
+  let mut manifest_path = None;
+  let mut labelled = Vec::new();
+  let mut any_training_options = false;
+
+  let mut args = args.iter();
+  while let Some(arg) = args.next() {
+    match arg.as_str() {
+      "-o" | "--output" => output = Some(option_value(arg, &mut args)?.to_owned()),
+      "--manifest" => manifest_path = Some(option_value(arg, &mut args)?.to_owned()),
+      "--priors" => {
+        settings.priors = parse_option(arg, option_value(arg, &mut args)?)?;
+        any_training_options = true;
+      },
+      _ if parse_training_option(arg, &mut args, &mut settings, &mut reading)? =>
+        any_training_options = true,
+      _ => labelled.push(parse_corpus(arg)?),
+    }
+  }
 
-struct Classifier {
-  features: HashMap<Digraph, Occurance>
-}
+  let mut corpora = reading.apply_to(labelled);
+
+  if let Some(path) = manifest_path {
+    // The manifest alone decides how the model is trained.
+    if !corpora.is_empty() || any_training_options {
+      return Err(CliError::Usage(
+        "give either --manifest or LANG=CORPUS arguments and training options, not both".into()));
+    }
+    let manifest = Manifest::load(&path).map_err(|err| CliError::Io(path, err))?;
+    corpora = manifest.corpora;
+    settings = manifest.settings;
+  }
 
+  if corpora.is_empty() {
+    return Err(CliError::Usage("expected --manifest or at least one LANG=CORPUS argument".into()));
+  }
 
-fn main() -> io::Result<()> {
-  let mut model = Classifier::new();
-  model.count_digraphs_in_file("itwêwina", Language::Crk);
-  model.count_digraphs_in_file("words", Language::Eng);
+  let (model, pruning) = train_on(&corpora, settings)?;
+  report_pruning(&pruning, model.settings());
 
-  model.prune_features();
+  let result = match output {
+    Some(ref path) => File::create(path)
+      .and_then(|file| model.save(BufWriter::new(file))),
+    None => model.save(io::stdout().lock()),
+  };
+  result.map_err(|err| CliError::Io(output.unwrap_or_else(|| "<stdout>".into()), err))
+}
 
-  use std::io;
-  use std::io::prelude::*;
+/**
+ * Classifies every word given on stdin.
+ */
+fn classify(args: &[String]) -> Result<(), CliError> {
+  let mut model_path = None;
+  let mut verbose = false;
+  let mut text = false;
+  let mut document = false;
+  let mut min_confidence = 0.0;
+  let mut priors: Option<Priors> = None;
+  let mut settings = Settings::default();
+  let mut reading = Reading::default();
+  let mut labelled = Vec::new();
+  let mut any_training_options = false;
+
+  let mut args = args.iter();
+  while let Some(arg) = args.next() {
+    match arg.as_str() {
+      "--model" => model_path = Some(option_value(arg, &mut args)?),
+      "-v" | "--verbose" => verbose = true,
+      "--text" => text = true,
+      "--document" => document = true,
+      "--min-confidence" => {
+        min_confidence = parse_option(arg, option_value(arg, &mut args)?)?;
+        if !(0.0..=1.0).contains(&min_confidence) {
+          return Err(CliError::Usage(format!("{} must be a number between 0 and 1", arg)));
+        }
+      },
+      "--priors" => priors = Some(parse_option(arg, option_value(arg, &mut args)?)?),
+      _ if parse_training_option(arg, &mut args, &mut settings, &mut reading)? =>
+        any_training_options = true,
+      _ => labelled.push(parse_corpus(arg)?),
+    }
+  }
+
+  let corpora = reading.apply_to(labelled);
+  let mut model = load_or_train(model_path, &corpora, settings, any_training_options)?;
+  model.set_min_confidence(min_confidence);
+  if let Some(priors) = priors {
+    model.set_priors(priors);
+  }
 
   let stdin = io::stdin();
+  if document {
+    let result = model.classify_document(stdin.lock())
+      .map_err(|err| CliError::Io("<stdin>".into(), err))?;
+
+    if verbose {
+      for score in &result.scores {
+        eprintln!("  P({}|document) = {:.6}\t(log-prior {:.4}, log-likelihood {:.4})",
+                  score.language, score.posterior, score.log_prior, score.log_likelihood);
+      }
+      eprintln!("  confidence = {:.6}", result.confidence);
+    }
+
+    match result.verdict {
+      Verdict::Language(language) => println!("document: {}", language),
+      Verdict::Unknown(reason) => println!("document: Unknown ({})", reason),
+    }
+    for &(language, count) in &result.counts {
+      println!("  {}: {}/{} words ({:.2}%)", language, count, result.words, percent(count, result.words));
+    }
+    println!("  unknown: {}/{} words ({:.2}%)",
+             result.unknown, result.words, percent(result.unknown, result.words));
+    return Ok(());
+  }
+
   for line in stdin.lock().lines() {
-    let word = line_to_word(&line.unwrap());
-    let guessed_lang = model.classify(&word);
+    let line = line.map_err(|err| CliError::Io("<stdin>".into(), err))?;
+    let inputs: Vec<&str> = if text { words(&line).collect() } else { vec![&line] };
+
+    for input in inputs {
+      let result = model.classify(input);
+
+      if verbose {
+        for score in &result.scores {
+          eprintln!("  P({}|{}) = {:.6}\t(log-prior {:.4}, log-likelihood {:.4})",
+                    score.language, result.word, score.posterior, score.log_prior, score.log_likelihood);
+        }
+        eprintln!("  confidence = {:.6}", result.confidence);
+      }
 
-    println!("{}: {:?}", word, guessed_lang);
+      match result.verdict {
+        Verdict::Language(language) => println!("{}: {}", result.word, language),
+        Verdict::Unknown(reason) => println!("{}: Unknown ({})", result.word, reason),
+      }
+    }
   }
 
   Ok(())
 }
 
-/// Gets rid of surrounding whitespace,
-/// removes circumflexes,
-/// and lowercase's everting.
-fn line_to_word(line: &str) -> String {
-  let mut buffer = String::new();
-  // Remove extraneous spaces and punctuation.
-  let word = line.trim_right_matches(|c| "!? \n".contains(c));
+/**
+ * Tags spans of each line given on stdin with their language.
+ */
+fn tag(args: &[String]) -> Result<(), CliError> {
+  let mut model_path = None;
+  let mut switch_penalty = DEFAULT_SWITCH_PENALTY;
+  let mut priors: Option<Priors> = None;
+  let mut settings = Settings::default();
+  let mut reading = Reading::default();
+  let mut labelled = Vec::new();
+  let mut any_training_options = false;
+
+  let mut args = args.iter();
+  while let Some(arg) = args.next() {
+    match arg.as_str() {
+      "--model" => model_path = Some(option_value(arg, &mut args)?),
+      "--switch-penalty" => {
+        switch_penalty = parse_option(arg, option_value(arg, &mut args)?)?;
+        if !switch_penalty.is_finite() || switch_penalty < 0.0 {
+          return Err(CliError::Usage(format!("{} must be a number, at least 0", arg)));
+        }
+      },
+      "--priors" => priors = Some(parse_option(arg, option_value(arg, &mut args)?)?),
+      _ if parse_training_option(arg, &mut args, &mut settings, &mut reading)? =>
+        any_training_options = true,
+      _ => labelled.push(parse_corpus(arg)?),
+    }
+  }
+
+  let corpora = reading.apply_to(labelled);
+  let mut model = load_or_train(model_path, &corpora, settings, any_training_options)?;
+  if let Some(priors) = priors {
+    model.set_priors(priors);
+  }
+
+  let stdin = io::stdin();
+  for line in stdin.lock().lines() {
+    let line = line.map_err(|err| CliError::Io("<stdin>".into(), err))?;
+    let words: Vec<&str> = words(&line).collect();
 
-  for ch in word.chars() {
-    // TODO: use a crate the provides NFD normalization,
-    // and simply remove \u{03xx} code points.
-    let ch = ch.to_lowercase().nth(0).unwrap();
-    buffer.push(match ch {
-      'â' => 'a',
-      'ê' => 'e',
-      'î' => 'i',
-      'ô' => 'o',
-      _ => ch,
-    })
+    let spans: Vec<String> = model.tag(&words, switch_penalty).iter()
+      .map(|span| format!("[{} {}]", span.language, words[span.start..span.end].join(" ")))
+      .collect();
+    println!("{}", spans.join(" "));
   }
 
-  buffer
+  Ok(())
 }
 
 /**
- * Counts digraphs in a word. Assumes the word has already been preprocessed.
+ * Reports how many words in each of the test files are classified correctly.
  */
-fn digraphs_of(text: &str) -> HashSet<Digraph> {
-  if text.is_empty() {
-    return HashSet::new();
+fn eval(args: &[String]) -> Result<(), CliError> {
+  let mut model_path = None;
+  let mut min_confidence = 0.0;
+  let mut priors: Option<Priors> = None;
+  let mut settings = Settings::default();
+  let mut reading = Reading::default();
+  let mut labelled = Vec::new();
+  let mut any_training_options = false;
+  let mut tests = Vec::new();
+
+  let mut args = args.iter();
+  while let Some(arg) = args.next() {
+    match arg.as_str() {
+      "--model" => model_path = Some(option_value(arg, &mut args)?),
+      "--min-confidence" => {
+        min_confidence = parse_option(arg, option_value(arg, &mut args)?)?;
+        if !(0.0..=1.0).contains(&min_confidence) {
+          return Err(CliError::Usage(format!("{} must be a number between 0 and 1", arg)));
+        }
+      },
+      "--priors" => priors = Some(parse_option(arg, option_value(arg, &mut args)?)?),
+      "--test" => tests.push(parse_labelled_file(option_value(arg, &mut args)?)?),
+      _ if parse_training_option(arg, &mut args, &mut settings, &mut reading)? =>
+        any_training_options = true,
+      _ => labelled.push(parse_corpus(arg)?),
+    }
   }
-  assert!(!text.ends_with('\n'));
 
-  let mut digraphs = HashSet::new();
+  if tests.is_empty() {
+    return Err(CliError::Usage("eval requires at least one --test LANG=FILE".into()));
+  }
 
-  // The first digraph always has includes the Start token.
-  let mut last_char = Token::Start;
-  for ch in text.chars() {
-    let this_char = Token::Char(ch);
-    digraphs.insert(Digraph(last_char, this_char));
-    last_char = this_char;
+  let corpora = reading.apply_to(labelled);
+  let mut model = load_or_train(model_path, &corpora, settings, any_training_options)?;
+  model.set_min_confidence(min_confidence);
+  if let Some(priors) = priors {
+    model.set_priors(priors);
   }
 
-  // Finalize by adding last character in the string.
-  digraphs.insert(Digraph(last_char, Token::End));
+  let mut total_correct = 0;
+  let mut total_unknown = 0;
+  let mut total = 0;
+  for test in &tests {
+    let file = File::open(&test.path).map_err(|err| io_error(test, err))?;
 
-  digraphs
-}
+    let mut correct = 0;
+    let mut unknown = 0;
+    let mut count = 0;
+    for line in BufReader::new(file).lines() {
+      let line = line.map_err(|err| io_error(test, err))?;
+      let result = model.classify(&line);
+      if result.word.is_empty() {
+        continue;
+      }
 
+      match result.verdict {
+        Verdict::Language(language) if language == test.language => correct += 1,
+        Verdict::Language(_) => (),
+        Verdict::Unknown(_) => unknown += 1,
+      }
+      count += 1;
+    }
 
-impl Classifier {
-  fn new() -> Classifier {
-    Classifier { features: HashMap::new() }
+    println!("{}: {}/{} correct ({:.2}%), {} unknown",
+             test.path.display(), correct, count, percent(correct, count), unknown);
+    total_correct += correct;
+    total_unknown += unknown;
+    total += count;
   }
 
-  /**
-   * Given a filename, gets a set of all of the digraphs present in each word.
-   * Use the "on_digraph" closure to increment the correct counter.
-   */
-  fn count_digraphs_in_file(&mut self, filename: &str, lang: Language) {
-    let file = File::open(filename).expect("file not found");
+  println!("overall: {}/{} correct ({:.2}%), {} unknown",
+           total_correct, total, percent(total_correct, total), total_unknown);
 
-    for line in BufReader::new(file).lines() {
-      let line = line.expect("Couldn't get line");
-      let word = line_to_word(&line);
-      for digraph in digraphs_of(&word).iter() {
-        let occ = self.features.entry(*digraph)
-          .or_insert(Occurance { crk: 0, eng: 0});
-        match lang {
-          Language::Crk => occ.crk += 1,
-          Language::Eng => occ.eng += 1,
-        };
-      }
-    }
+  Ok(())
+}
+
+/**
+ * Rewrites the Cree syllabics read from stdin in SRO, line by line.
+ */
+fn transliterate_stdin(args: &[String]) -> Result<(), CliError> {
+  if let Some(arg) = args.first() {
+    return Err(CliError::Usage(format!("unexpected argument '{}'", arg)));
   }
 
-  /**
-   * Removes unhelpful features.
-   */
-  fn prune_features(&mut self) {
-    // "Unhelpful" features are digraphs that have only been witnessed once, ever.
-    // Remove them, since they don't add much when classifying.
-    self.features.retain(|_digraph, occ| occ.total() > 1);
+  let stdin = io::stdin();
+  for line in stdin.lock().lines() {
+    let line = line.map_err(|err| CliError::Io("<stdin>".into(), err))?;
+    println!("{}", transliterate(&line));
   }
 
-  fn classify(&self, word: &str) -> Language {
-    let mut log_prob_crk: f64 = 0.0;
-    let mut log_prob_eng: f64 = 0.0;
+  Ok(())
+}
 
-    for digraph in digraphs_of(word) {
-      // Skip digraphs we've never seen.
-      if !self.features.contains_key(&digraph) {
-        continue;
-      }
+/**
+ * Trains a new model on each of the given corpora, also returning how many
+ * n-grams were pruned.
+ */
+fn train_on(corpora: &[Corpus], settings: Settings) -> Result<(Classifier, Pruning), CliError> {
+  settings.validate().map_err(CliError::Usage)?;
+
+  let mut model = Classifier::with_settings(settings);
+  for corpus in corpora {
+    let skipped = match model.count_ngrams_in_corpus(corpus) {
+      Ok(skipped) => skipped,
+      Err(CorpusError::EmptyCorpus { path, skipped }) => {
+        report_skipped(&skipped);
+        return Err(CliError::Corpus(CorpusError::EmptyCorpus { path, skipped }));
+      },
+      Err(err) => return Err(CliError::Corpus(err)),
+    };
+    report_skipped(&skipped);
+  }
 
-      log_prob_crk += self.log_prob(digraph, Language::Crk).expect("digraph does not exist");
-      log_prob_eng += self.log_prob(digraph, Language::Eng).expect("digraph does not exist");
-    }
+  let pruning = model.prune_features();
 
-    println!("  P(crk|{}) = {}", word, log_prob_crk.exp());
-    println!("  P(eng|{}) = {}", word, log_prob_eng.exp());
+  Ok((model, pruning))
+}
 
-    if log_prob_crk > log_prob_eng {
-      Language::Crk
-    } else {
-      Language::Eng
-    }
+/**
+ * Tells the user (on stderr) about each line of a lenient corpus that was skipped.
+ */
+fn report_skipped(skipped: &[CorpusError]) {
+  for err in skipped {
+    eprintln!("crk-or-eng: skipped {}", err);
   }
+}
 
-  fn log_prob(&self, digraph: Digraph, language: Language) -> Option<f64> {
-    if let Some(occurance) = self.features.get(&digraph) {
-      let numerator: f64 = (occurance.of(language) + 1).into();
-      let denominator: f64 = (occurance.total() + self.num_features()).into();
+/**
+ * Tells the user (on stderr) how many n-grams were removed, and why.
+ */
+fn report_pruning(pruning: &Pruning, settings: &Settings) {
+  eprintln!("kept {} of {} n-grams", pruning.after(), pruning.before);
+  eprintln!("  {} seen fewer than {} times", pruning.below_min_count, settings.min_count);
+  if let Some(selection) = settings.selection {
+    eprintln!("  {} not in the top {} by {}", pruning.not_selected, selection.keep, selection.criterion);
+  }
+}
 
-      Some(numerator.ln() - denominator.ln())
-    } else {
-      None
-    }
+/**
+ * Either loads a saved model, or trains one on the given corpora---but not both!
+ */
+fn load_or_train(model_path: Option<&str>, corpora: &[Corpus], settings: Settings, any_training_options: bool)
+  -> Result<Classifier, CliError>
+{
+  match model_path {
+    Some(_) if !corpora.is_empty() =>
+      Err(CliError::Usage("give either --model or LANG=CORPUS arguments, not both".into())),
+    // A saved model was trained with its own settings.
+    Some(_) if any_training_options =>
+      Err(CliError::Usage("training options can't change a model given with --model".into())),
+    Some(path) => File::open(path)
+      .and_then(|file| Classifier::load(BufReader::new(file)))
+      .map_err(|err| CliError::Io(path.to_owned(), err)),
+    None if corpora.is_empty() =>
+      Err(CliError::Usage("expected either --model or at least one LANG=CORPUS argument".into())),
+    None => train_on(corpora, settings).map(|(model, _pruning)| model),
   }
+}
 
-  fn num_features(&self) -> u32 {
-    self.features.len() as u32
+/**
+ * Handles an option that changes how the model is trained (or how its corpora
+ * are read).
+ * Returns false if the argument is not such an option.
+ */
+fn parse_training_option<'a, I>(arg: &str, args: &mut I, settings: &mut Settings, reading: &mut Reading)
+  -> Result<bool, CliError>
+  where I: Iterator<Item = &'a String>
+{
+  match arg {
+    "--order" => settings.order = parse_option(arg, option_value(arg, args)?)?,
+    "--event-model" => settings.event_model = parse_option(arg, option_value(arg, args)?)?,
+    "--smoothing" => settings.smoothing = parse_option(arg, option_value(arg, args)?)?,
+    "--estimation" => settings.estimation = parse_option(arg, option_value(arg, args)?)?,
+    "--min-count" => settings.min_count = parse_option(arg, option_value(arg, args)?)?,
+    "--select" => settings.selection = Some(parse_option(arg, option_value(arg, args)?)?),
+    "--unseen" => settings.unseen = parse_option(arg, option_value(arg, args)?)?,
+    "--format" => reading.format = parse_option(arg, option_value(arg, args)?)?,
+    "--lenient" => reading.lenient = true,
+    "--keep-vowel-length" => settings.normalization.keep_vowel_length = true,
+    "--case-fold" => settings.normalization.case_fold = true,
+    _ => return Ok(false),
   }
+
+  Ok(true)
 }
 
+/**
+ * Gets the value that must follow an option.
+ */
+fn option_value<'a, I>(option: &str, args: &mut I) -> Result<&'a str, CliError>
+  where I: Iterator<Item = &'a String>
+{
+  args.next()
+    .map(|value| value.as_str())
+    .ok_or_else(|| CliError::Usage(format!("{} requires a value", option)))
+}
+
+/**
+ * Parses the value of an option, such as a number.
+ */
+fn parse_option<T: FromStr>(option: &str, value: &str) -> Result<T, CliError> {
+  value.parse()
+    .map_err(|_| CliError::Usage(format!("invalid value '{}' for {}", value, option)))
+}
 
-impl Occurance {
-  fn total(&self) -> u32 {
-    self.crk + self.eng
+/**
+ * Parses an argument of the form LANG=FILE.
+ */
+fn parse_labelled_file(arg: &str) -> Result<Corpus, CliError> {
+  let (lang, path) = split_labelled(arg)?;
+  Ok(Corpus::new(parse_language(lang, arg)?, path))
+}
+
+/**
+ * Parses a corpus to train on: LANG=FILE, or LANG:FORMAT=FILE if the corpus is
+ * written in a format of its own.
+ */
+fn parse_corpus(arg: &str) -> Result<(Corpus, Option<Format>), CliError> {
+  let (label, path) = split_labelled(arg)?;
+  let mut parts = label.splitn(2, ':');
+  let lang = parse_language(parts.next().unwrap_or_default(), arg)?;
+  let format = match parts.next() {
+    Some(format) => Some(format.parse()
+      .map_err(|_| CliError::Usage(format!("invalid format '{}' in '{}'", format, arg)))?),
+    None => None,
+  };
+
+  Ok((Corpus::new(lang, path), format))
+}
+
+/**
+ * Splits LANG=FILE into its label and path.
+ */
+fn split_labelled(arg: &str) -> Result<(&str, &str), CliError> {
+  if arg.starts_with('-') {
+    return Err(CliError::Usage(format!("unknown option '{}'", arg)));
   }
 
-  fn of(&self, language: Language) -> u32 {
-    match language {
-      Language::Crk => self.crk,
-      Language::Eng => self.eng,
-    }
+  let mut parts = arg.splitn(2, '=');
+  match (parts.next(), parts.next()) {
+    (Some(label), Some(path)) if !path.is_empty() => Ok((label, path)),
+    _ => Err(CliError::Usage(format!("expected LANG=FILE, got '{}'", arg))),
   }
 }
 
+fn parse_language(lang: &str, arg: &str) -> Result<Language, CliError> {
+  lang.parse().map_err(|_| CliError::Usage(format!("invalid language '{}' in '{}'", lang, arg)))
+}
 
 /**
- * Displays a character. Note that this will panic if the characters are either
- * '^' or '$' as those are used to indicate the Start and End tokens, respectively.
+ * An I/O error that happened while reading the corpus.
  */
-impl fmt::Display for Token {
-  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-    write!(f, "{}", match *self {
-      Token::Start => '^',
-      Token::End => '$',
-      Token::Char(c) => {
-        /* Make sure the meaning of '^' and '$' is unambiguous---
-         * we wouldn't want our shorthand to be an actual character! */
-        assert!(c != '^' || c != '$');
-        c
-      },
-    })
+fn io_error(corpus: &Corpus, err: io::Error) -> CliError {
+  CliError::Io(corpus.path.display().to_string(), err)
+}
+
+fn percent(numerator: usize, denominator: usize) -> f64 {
+  if denominator == 0 {
+    return 0.0;
   }
+
+  100.0 * numerator as f64 / denominator as f64
 }
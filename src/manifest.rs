@@ -0,0 +1,297 @@
+/*
+ * Copyright (C) 2018 Eddie Antonio Santos <easantos@ualberta.ca>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+
+//! Training manifests, which list the corpora and settings to train a model
+//! with, so that it can be trained exactly the same way again.
+
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use language::Language;
+use settings::{parse_yes_or_no, Settings};
+
+/**
+ * Everything needed to train a model, usually read from a file like this:
+ *
+ * ```text
+ * # nêhiyawêwin vs. English
+ * order 3
+ * event-model multinomial
+ * smoothing kneser-ney:0.75
+ * select chi-square:5000
+ *
+ * corpus crk itwêwina
+ * corpus crk social media.txt
+ *   weight 0.5
+ * corpus crk frequencies.tsv
+ *   format counts:log
+ *   lenient yes
+ * corpus eng words
+ * ```
+ *
+ * Blank lines and lines starting with '#' are ignored. Every other line is
+ * "key value": either a setting, written just as in a saved model (see
+ * Classifier::save()), or "corpus" followed by a language and the path to a
+ * word list. Indented lines describe the corpus above them: "weight W" counts
+ * each of its words W times, "format F" says how it is written (see Format),
+ * and "lenient yes" skips lines that can't be read instead of giving up.
+ * Settings that are not given keep their defaults. Note that weights and
+ * (damped) counts also apply to min-count: with "counts:log", an n-gram seen
+ * in two words counted once each totals 2 ln 2, or about 1.39.
+ */
+#[derive(PartialEq, Debug, Clone)]
+pub struct Manifest {
+  pub corpora: Vec<Corpus>,
+  pub settings: Settings,
+}
+
+/**
+ * A list of words in one language.
+ */
+#[derive(PartialEq, Debug, Clone)]
+pub struct Corpus {
+  pub language: Language,
+  pub path: PathBuf,
+  /// How much each word counts; 1.0 unless given.
+  pub weight: f64,
+  pub format: Format,
+  /// Whether to skip lines that can't be read, rather than give up.
+  pub lenient: bool,
+}
+
+/**
+ * How the words of a corpus are written.
+ */
+#[derive(PartialEq, Debug, Default, Copy, Clone)]
+pub enum Format {
+  /// One word per line.
+  #[default]
+  Words,
+  /// One word per line, followed by a tab and how many times it occurs (or any
+  /// other non-negative weight), as in a frequency list. The count is damped
+  /// before it is used as the word's weight.
+  Counts(Damping),
+}
+
+/**
+ * How the count of a word in a frequency list becomes its weight.
+ */
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum Damping {
+  /// The count itself.
+  None,
+  /// ln(1 + count), so that frequent words like "êkwa" count for more, but
+  /// don't drown out everything else.
+  Log,
+  /// The square root of the count; between no damping and log damping.
+  Sqrt,
+}
+
+
+impl Manifest {
+  /**
+   * Reads a manifest file. Corpora are found relative to the manifest itself.
+   */
+  pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Manifest> {
+    let path = path.as_ref();
+    let mut manifest = Manifest::parse(BufReader::new(File::open(path)?))?;
+
+    let directory = path.parent().unwrap_or_else(|| Path::new(""));
+    for corpus in &mut manifest.corpora {
+      corpus.path = directory.join(&corpus.path);
+    }
+
+    Ok(manifest)
+  }
+
+  /**
+   * Reads a manifest, leaving the paths of the corpora as they are written.
+   */
+  pub fn parse<R: BufRead>(reader: R) -> io::Result<Manifest> {
+    let mut corpora: Vec<Corpus> = Vec::new();
+    let mut settings = Settings::default();
+
+    for (index, line) in reader.lines().enumerate() {
+      let line = line?;
+      let invalid = |message: String| invalid_manifest(&format!("line {}: {}", index + 1, message));
+
+      let trimmed = line.trim();
+      if trimmed.is_empty() || trimmed.starts_with('#') {
+        continue;
+      }
+
+      let mut parts = trimmed.splitn(2, char::is_whitespace);
+      let (key, value) = match (parts.next(), parts.next()) {
+        (Some(key), Some(value)) => (key, value.trim_start()),
+        _ => return Err(invalid(format!("expected key and value, got '{}'", trimmed))),
+      };
+
+      if line.starts_with(char::is_whitespace) {
+        let corpus = corpora.last_mut()
+          .ok_or_else(|| invalid("indented line does not follow a corpus".into()))?;
+        corpus.set(key, value).map_err(&invalid)?;
+      } else if key == "corpus" {
+        corpora.push(Corpus::parse(value).map_err(&invalid)?);
+      } else {
+        settings.set(key, value).map_err(&invalid)?;
+      }
+    }
+
+    if corpora.is_empty() {
+      return Err(invalid_manifest("no corpora given"));
+    }
+    settings.validate().map_err(|message| invalid_manifest(&message))?;
+
+    Ok(Manifest { corpora, settings })
+  }
+}
+
+
+impl Corpus {
+  pub fn new<P: Into<PathBuf>>(language: Language, path: P) -> Corpus {
+    Corpus { language, path: path.into(), weight: 1.0, format: Format::default(), lenient: false }
+  }
+
+  /**
+   * Parses a language and path, separated by a space.
+   */
+  fn parse(text: &str) -> Result<Corpus, String> {
+    let mut parts = text.splitn(2, char::is_whitespace);
+    match (parts.next(), parts.next()) {
+      (Some(language), Some(path)) => {
+        let language = language.parse()
+          .map_err(|_| format!("invalid language '{}'", language))?;
+        Ok(Corpus::new(language, path.trim_start()))
+      },
+      _ => Err(format!("expected a language and a path, got '{}'", text)),
+    }
+  }
+
+  fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+    match key {
+      "weight" => match value.parse() {
+        Ok(weight) if weight > 0.0 && f64::is_finite(weight) => self.weight = weight,
+        _ => return Err(format!("invalid weight '{}'", value)),
+      },
+      "format" => self.format = value.parse().map_err(|_| format!("invalid format '{}'", value))?,
+      "lenient" => self.lenient = parse_yes_or_no(key, value)?,
+      _ => return Err(format!("unknown corpus option '{}'", key)),
+    }
+
+    Ok(())
+  }
+}
+
+
+
+impl Format {
+  /**
+   * Splits a line of the corpus into the word and its weight.
+   */
+  pub(crate) fn parse_line<'a>(&self, line: &'a str) -> Result<(&'a str, f64), String> {
+    let damping = match *self {
+      Format::Words => return Ok((line, 1.0)),
+      Format::Counts(damping) => damping,
+    };
+    if line.trim().is_empty() {
+      return Ok((line, 0.0));
+    }
+
+    let mut fields = line.rsplitn(2, '\t');
+    let (count, word) = match (fields.next(), fields.next()) {
+      (Some(count), Some(word)) => (count, word),
+      _ => return Err(format!("expected a word, a tab, and a count; got '{}'", line)),
+    };
+    match count.trim().parse::<f64>() {
+      Ok(count) if count >= 0.0 && count.is_finite() => Ok((word, damping.apply(count))),
+      _ => Err(format!("invalid count '{}'", count)),
+    }
+  }
+}
+
+
+impl Damping {
+  fn apply(&self, count: f64) -> f64 {
+    match *self {
+      Damping::None => count,
+      Damping::Log => count.ln_1p(),
+      Damping::Sqrt => count.sqrt(),
+    }
+  }
+}
+
+
+/**
+ * Parses "words", or "counts" optionally followed by the damping, e.g.,
+ * "counts:log" or "counts:sqrt".
+ */
+impl FromStr for Format {
+  type Err = ();
+
+  fn from_str(s: &str) -> Result<Format, ()> {
+    match s {
+      "words" => Ok(Format::Words),
+      "counts" => Ok(Format::Counts(Damping::None)),
+      _ => Ok(Format::Counts(s.strip_prefix("counts:").ok_or(())?.parse()?)),
+    }
+  }
+}
+
+
+impl FromStr for Damping {
+  type Err = ();
+
+  fn from_str(s: &str) -> Result<Damping, ()> {
+    match s {
+      "none" => Ok(Damping::None),
+      "log" => Ok(Damping::Log),
+      "sqrt" => Ok(Damping::Sqrt),
+      _ => Err(()),
+    }
+  }
+}
+
+
+impl fmt::Display for Format {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      Format::Words => f.write_str("words"),
+      Format::Counts(Damping::None) => f.write_str("counts"),
+      Format::Counts(damping) => write!(f, "counts:{}", damping),
+    }
+  }
+}
+
+
+impl fmt::Display for Damping {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    f.write_str(match *self {
+      Damping::None => "none",
+      Damping::Log => "log",
+      Damping::Sqrt => "sqrt",
+    })
+  }
+}
+
+
+fn invalid_manifest(message: &str) -> io::Error {
+  io::Error::new(io::ErrorKind::InvalidData, message)
+}
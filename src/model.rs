@@ -0,0 +1,424 @@
+/*
+ * Copyright (C) 2018 Eddie Antonio Santos <easantos@ualberta.ca>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+
+//! Saving and loading trained models.
+
+use std::io::{self, BufRead, Write};
+use std::str::FromStr;
+
+use classifier::{Classifier, Occurance};
+use language::Language;
+use settings::{Estimation, EventModel, Priors, Settings, Unseen};
+use features::{NGram, Token};
+
+/// The first line of every saved model. The version number follows it.
+const MODEL_MAGIC: &str = "crk-or-eng model";
+/// The version of the model format written by Classifier::save.
+const MODEL_VERSION: u32 = 4;
+
+
+impl Classifier {
+  /**
+   * Writes the model in the following line-based format:
+   *
+   * ```text
+   * crk-or-eng model 4
+   * languages crk eng
+   * transliterate-syllabics yes
+   * lowercase yes
+   * case-fold no
+   * strip-diacritics yes
+   * keep-vowel-length no
+   * order 2
+   * event-model presence
+   * estimation likelihood
+   * smoothing lidstone:1
+   * unseen backoff
+   * priors corpus
+   * min-count 2
+   * select all
+   * words 3000 3000
+   * features 2
+   * ^t      12      30
+   * wa      103     7
+   * unigrams 3
+   * a       2311    1893
+   * t       1020    1544
+   * w       1290    201
+   * ```
+   *
+   * The first line is the magic header followed by the format version
+   * (version 1 models predate the estimation setting, and always use legacy
+   * estimation; version 2 models skip unseen n-grams; and versions 1 to 3
+   * have no "languages" line, as they were always crk and eng). Next are the
+   * languages, the settings, one "key value" per line, and the number of
+   * words counted in each language, in the same order as the languages.
+   * Finally, "features N" is followed by N lines of an n-gram and its count
+   * in each language, separated by tabs, and "unigrams N" by the counts of
+   * each character in the same way.
+   * In an n-gram, '^' and '$' stand for the start and end of the word; a
+   * literal '^', '$' or '\' is preceded by a backslash, and tabs and
+   * newlines are written as "\t" and "\n".
+   *
+   * A model that hasn't counted any words yet has no languages, and can't be
+   * saved.
+   */
+  pub fn save<W: Write>(&self, mut writer: W) -> io::Result<()> {
+    if self.languages.is_empty() {
+      return Err(io::Error::new(io::ErrorKind::InvalidInput, "no words have been counted"));
+    }
+
+    let settings = &self.settings;
+    writeln!(writer, "{} {}", MODEL_MAGIC, MODEL_VERSION)?;
+    let labels: Vec<_> = self.languages.iter().map(Language::as_str).collect();
+    writeln!(writer, "languages {}", labels.join(" "))?;
+    writeln!(writer, "transliterate-syllabics {}", yes_or_no(settings.normalization.transliterate_syllabics))?;
+    writeln!(writer, "lowercase {}", yes_or_no(settings.normalization.lowercase))?;
+    writeln!(writer, "case-fold {}", yes_or_no(settings.normalization.case_fold))?;
+    writeln!(writer, "strip-diacritics {}", yes_or_no(settings.normalization.strip_diacritics))?;
+    writeln!(writer, "keep-vowel-length {}", yes_or_no(settings.normalization.keep_vowel_length))?;
+    writeln!(writer, "order {}", settings.order)?;
+    writeln!(writer, "event-model {}", settings.event_model)?;
+    writeln!(writer, "estimation {}", settings.estimation)?;
+    writeln!(writer, "smoothing {}", settings.smoothing)?;
+    writeln!(writer, "unseen {}", settings.unseen)?;
+    writeln!(writer, "priors {}", settings.priors)?;
+    writeln!(writer, "min-count {}", settings.min_count)?;
+    match settings.selection {
+      Some(selection) => writeln!(writer, "select {}", selection)?,
+      None => writeln!(writer, "select all")?,
+    }
+    writeln!(writer, "words {}", self.format_counts(&self.words, " "))?;
+
+    // Sort the n-grams so that the same model is always saved the same way.
+    let mut ngrams: Vec<_> = self.features.iter()
+      .map(|(ngram, occ)| (encode_ngram(ngram), occ))
+      .collect();
+    ngrams.sort_by(|a, b| a.0.cmp(&b.0));
+
+    writeln!(writer, "features {}", ngrams.len())?;
+    for (ngram, occ) in ngrams {
+      writeln!(writer, "{}\t{}", ngram, self.format_counts(occ, "\t"))?;
+    }
+
+    let mut unigrams: Vec<_> = self.unigrams.iter()
+      .map(|(&ch, occ)| (encode_ngram(&NGram(vec![Token::Char(ch)])), occ))
+      .collect();
+    unigrams.sort_by(|a, b| a.0.cmp(&b.0));
+
+    writeln!(writer, "unigrams {}", unigrams.len())?;
+    for (unigram, occ) in unigrams {
+      writeln!(writer, "{}\t{}", unigram, self.format_counts(occ, "\t"))?;
+    }
+
+    writer.flush()
+  }
+
+  /**
+   * Reads a model written by Classifier::save().
+   */
+  pub fn load<R: BufRead>(reader: R) -> io::Result<Classifier> {
+    let mut lines = reader.lines();
+    let mut next_line = || -> io::Result<String> {
+      lines.next().unwrap_or_else(|| Err(invalid_model("unexpected end of file")))
+    };
+
+    let header = next_line()?;
+    let version = header.strip_prefix(MODEL_MAGIC)
+      .map(str::trim)
+      .ok_or_else(|| invalid_model("not a crk-or-eng model"))?;
+    let version: u32 = match version.parse() {
+      Ok(version) if (1..=MODEL_VERSION).contains(&version) => version,
+      _ => return Err(invalid_model(&format!("unsupported model version '{}'", version))),
+    };
+
+    let mut settings = Settings::default();
+    if version == 1 {
+      settings.estimation = Estimation::Legacy;
+    }
+    // Models saved before priors were introduced assumed they were uniform.
+    settings.priors = Priors::Uniform;
+    // ...and before the unseen policy, unseen n-grams were always skipped.
+    settings.unseen = Unseen::Skip;
+    // ...and before any other languages were supported, there were only two.
+    let mut languages = vec![Language::CRK, Language::ENG];
+    let mut words = None;
+    let num_features = loop {
+      let line = next_line()?;
+      let (key, value) = split_pair(&line, ' ')?;
+      match key {
+        "languages" => languages = parse_languages(value)?,
+        "words" => words = Some(value.to_owned()),
+        "features" => break parse_number(value)?,
+        _ => settings.set(key, value).map_err(|message| invalid_model(&message))?,
+      }
+    };
+
+    settings.validate().map_err(|message| invalid_model(&message))?;
+
+    let order = settings.order;
+    let num_languages = languages.len();
+    let mut model = Classifier::with_settings(settings);
+    model.languages = languages;
+    if let Some(words) = words {
+      model.words = parse_counts(words.split(' '), num_languages)
+        .map_err(|_| invalid_model(&format!("expected {} word counts, got '{}'", num_languages, words)))?;
+    }
+    // Words are counted once each in these models, so no n-gram can appear in
+    // more words than there are.
+    // (Version 1 models didn't count words at all.)
+    let once_per_word = model.settings.event_model != EventModel::Multinomial && model.words.total() > 0.0;
+    for _ in 0..num_features {
+      let line = next_line()?;
+      let (ngram, occ) = parse_feature(&line, order, num_languages)?;
+      if once_per_word && (0..num_languages).any(|index| occ.of(index) > model.words.of(index)) {
+        return Err(invalid_model(&format!("'{}' appears in more words than were counted", line)));
+      }
+      model.features.insert(ngram, occ);
+    }
+
+    // Older models have no unigram counts.
+    let num_unigrams = match lines.next() {
+      Some(line) => {
+        let line = line?;
+        match split_pair(&line, ' ')? {
+          ("unigrams", value) => parse_number(value)?,
+          _ => return Err(invalid_model(&format!("expected unigrams, got '{}'", line))),
+        }
+      },
+      None => 0,
+    };
+    for _ in 0..num_unigrams {
+      let line = lines.next().unwrap_or_else(|| Err(invalid_model("unexpected end of file")))?;
+      let (unigram, occ) = parse_feature(&line, 1, num_languages)?;
+      match unigram.0[0] {
+        Token::Char(ch) => model.unigrams.insert(ch, occ),
+        _ => return Err(invalid_model(&format!("'{}' is not a character", line))),
+      };
+    }
+
+    Ok(model)
+  }
+
+  /**
+   * Writes the count for every language, with the separator in between.
+   */
+  fn format_counts(&self, occ: &Occurance, separator: &str) -> String {
+    let counts: Vec<_> = (0..self.languages.len())
+      .map(|index| occ.of(index).to_string())
+      .collect();
+    counts.join(separator)
+  }
+}
+
+
+/**
+ * Parses a space-separated list of distinct languages.
+ */
+fn parse_languages(text: &str) -> io::Result<Vec<Language>> {
+  let mut languages = Vec::new();
+  for label in text.split(' ') {
+    let language = parse_setting("language", label)?;
+    if languages.contains(&language) {
+      return Err(invalid_model(&format!("language '{}' appears twice", language)));
+    }
+    languages.push(language);
+  }
+
+  Ok(languages)
+}
+
+/**
+ * Parses a line of tab-separated n-gram and count in each language.
+ */
+fn parse_feature(line: &str, order: usize, num_languages: usize) -> io::Result<(NGram, Occurance)> {
+  let mut fields = line.split('\t');
+  let ngram = fields.next().unwrap_or_default();
+  let occ = parse_counts(fields, num_languages)
+    .map_err(|_| invalid_model(&format!("malformed feature '{}'", line)))?;
+
+  Ok((decode_ngram(ngram, order)?, occ))
+}
+
+/**
+ * Parses exactly one count for each language, none of them negative.
+ */
+fn parse_counts<'a, I: Iterator<Item = &'a str>>(fields: I, num_languages: usize) -> io::Result<Occurance> {
+  let counts = fields.map(parse_number).collect::<io::Result<Vec<f64>>>()?;
+  if counts.len() != num_languages {
+    return Err(invalid_model("wrong number of counts"));
+  }
+  if counts.iter().any(|&count| !count.is_finite() || count < 0.0) {
+    return Err(invalid_model("counts must be finite and not negative"));
+  }
+
+  Ok(Occurance::from_counts(counts))
+}
+
+/**
+ * Writes an n-gram such that it can be read back by decode_ngram().
+ */
+fn encode_ngram(ngram: &NGram) -> String {
+  let mut buffer = String::new();
+  for token in &ngram.0 {
+    match *token {
+      Token::Start => buffer.push('^'),
+      Token::End => buffer.push('$'),
+      Token::Char('\t') => buffer.push_str("\\t"),
+      Token::Char('\n') => buffer.push_str("\\n"),
+      Token::Char(c @ '^') | Token::Char(c @ '$') | Token::Char(c @ '\\') => {
+        buffer.push('\\');
+        buffer.push(c);
+      },
+      Token::Char(c) => buffer.push(c),
+    }
+  }
+
+  buffer
+}
+
+fn decode_ngram(text: &str, order: usize) -> io::Result<NGram> {
+  let mut tokens = Vec::new();
+  let mut chars = text.chars();
+  while let Some(ch) = chars.next() {
+    tokens.push(match ch {
+      '^' => Token::Start,
+      '$' => Token::End,
+      '\\' => match chars.next() {
+        Some('t') => Token::Char('\t'),
+        Some('n') => Token::Char('\n'),
+        Some(c @ '^') | Some(c @ '$') | Some(c @ '\\') => Token::Char(c),
+        _ => return Err(invalid_model(&format!("invalid escape in '{}'", text))),
+      },
+      c => Token::Char(c),
+    });
+  }
+
+  if tokens.len() != order {
+    return Err(invalid_model(&format!("'{}' is not an n-gram of order {}", text, order)));
+  }
+
+  Ok(NGram(tokens))
+}
+
+fn invalid_model(message: &str) -> io::Error {
+  io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn split_pair(line: &str, separator: char) -> io::Result<(&str, &str)> {
+  let mut parts = line.splitn(2, separator);
+  match (parts.next(), parts.next()) {
+    (Some(key), Some(value)) => Ok((key, value)),
+    _ => Err(invalid_model(&format!("expected key and value, got '{}'", line))),
+  }
+}
+
+fn parse_number<T: FromStr>(text: &str) -> io::Result<T> {
+  text.parse().map_err(|_| invalid_model(&format!("invalid number '{}'", text)))
+}
+
+fn parse_setting<T: FromStr>(key: &str, value: &str) -> io::Result<T> {
+  value.parse().map_err(|_| invalid_model(&format!("invalid {} '{}'", key, value)))
+}
+
+fn yes_or_no(value: bool) -> &'static str {
+  if value { "yes" } else { "no" }
+}
+
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn token_ngram(chars: &str) -> NGram {
+    NGram(chars.chars().map(Token::Char).collect())
+  }
+
+  #[test]
+  fn saved_model_loads_back_the_same() {
+    let mut model = Classifier::new();
+    for word in &["a^b", "c$d", "e\\f", "g\th", "maskwa"] {
+      model.count_ngrams_in_word(word, Language::CRK);
+    }
+    for word in &["x\ty", "the", "\\$^", "a^b"] {
+      model.count_ngrams_in_word(word, Language::ENG);
+    }
+
+    let mut saved = Vec::new();
+    model.save(&mut saved).unwrap();
+    let loaded = Classifier::load(&saved[..]).unwrap();
+
+    assert_eq!(loaded.settings, model.settings);
+    assert_eq!(loaded.languages, model.languages);
+    for index in 0..2 {
+      assert_eq!(loaded.words.of(index), model.words.of(index));
+    }
+    assert_eq!(loaded.features.len(), model.features.len());
+    for (ngram, occ) in &model.features {
+      let loaded_occ = &loaded.features[ngram];
+      for index in 0..2 {
+        assert_eq!(loaded_occ.of(index), occ.of(index), "count of {:?}", ngram);
+      }
+    }
+    assert_eq!(loaded.unigrams.len(), model.unigrams.len());
+    for ngram in &["^b", "$d", "\\f", "g\t"] {
+      assert!(loaded.features.contains_key(&token_ngram(ngram)), "{:?} is missing", ngram);
+    }
+
+    for word in &["a^b", "e\\f", "x\ty", "maskwa", "the", "zzz"] {
+      let (expected, actual) = (model.classify(word), loaded.classify(word));
+      assert_eq!(actual.verdict, expected.verdict, "{:?}", word);
+      for (actual, expected) in actual.scores.iter().zip(&expected.scores) {
+        assert_eq!(actual.language, expected.language);
+        // Totals are summed in whatever order the n-grams are stored, so allow
+        // for rounding.
+        assert!((actual.log_likelihood - expected.log_likelihood).abs() < 1e-9,
+                "{:?} scores {} instead of {}", word, actual.log_likelihood, expected.log_likelihood);
+      }
+    }
+  }
+
+  #[test]
+  fn untrained_model_is_not_saved() {
+    let mut saved = Vec::new();
+    assert!(Classifier::new().save(&mut saved).is_err());
+    assert!(saved.is_empty());
+  }
+
+  #[test]
+  fn loads_version_1_model() {
+    let saved = "crk-or-eng model 1\n\
+                 lowercase yes\n\
+                 strip-diacritics yes\n\
+                 min-count 2\n\
+                 features 4\n\
+                 ^m\t3\t0\n\
+                 ma\t3\t1\n\
+                 ^t\t0\t3\n\
+                 th\t0\t3\n";
+    let model = Classifier::load(saved.as_bytes()).unwrap();
+
+    assert_eq!(model.languages, vec![Language::CRK, Language::ENG]);
+    assert_eq!(model.settings.estimation, Estimation::Legacy);
+    assert_eq!(model.settings.priors, Priors::Uniform);
+    assert_eq!(model.settings.unseen, Unseen::Skip);
+    assert_eq!(model.features.len(), 4);
+    assert_eq!(model.classify("maskwa").language(), Some(Language::CRK));
+    assert_eq!(model.classify("the").language(), Some(Language::ENG));
+  }
+}
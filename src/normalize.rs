@@ -0,0 +1,156 @@
+/*
+ * Copyright (C) 2018 Eddie Antonio Santos <easantos@ualberta.ca>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use caseless::default_case_fold_str;
+use unicode_normalization::UnicodeNormalization;
+use unicode_normalization::char::is_combining_mark;
+
+use syllabics::transliterate;
+
+/**
+ * How words are preprocessed before extracting n-grams.
+ */
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub struct Normalization {
+  /// Rewrite Cree syllabics in SRO first, so that ᓀᐦᐃᔭᐍᐏᐣ becomes nêhiyawêwin.
+  pub transliterate_syllabics: bool,
+  /// Lowercase the whole word, so that İ becomes i̇ and Σ at the end of a
+  /// word becomes ς.
+  pub lowercase: bool,
+  /// Fold case fully instead, as for caseless matching, so that ß and ẞ both
+  /// become ss, and ς becomes σ.
+  pub case_fold: bool,
+  /// Remove diacritics, so that â, ā, and á all become a.
+  pub strip_diacritics: bool,
+  /// Keep the marks of long vowels, even when stripping other diacritics,
+  /// writing macrons as circumflexes, so that â and ā both become â.
+  pub keep_vowel_length: bool,
+}
+
+/// Marks long vowels in Standard Roman Orthography (SRO).
+const COMBINING_CIRCUMFLEX: char = '\u{0302}';
+/// Marks long vowels in some other orthographies.
+const COMBINING_MACRON: char = '\u{0304}';
+
+
+/**
+ * Gets rid of trailing whitespace and punctuation, transliterates syllabics,
+ * lowercases everything, and removes diacritics (unless told otherwise).
+ *
+ * Case is changed over the whole word, rather than character by character,
+ * so that characters whose lowercase is longer than one character keep all
+ * of it, and Greek sigma is lowercased depending on where it is in the word.
+ *
+ * Diacritics are removed by decomposing the word (NFD) and dropping every
+ * combining mark, so that precomposed and combining circumflexes, macrons,
+ * and acute accents are all treated the same way. When vowel length is kept,
+ * a circumflex or macron on a, e, i, or o survives as a circumflex. Whatever
+ * is left is recomposed (NFC), so that a word is always written the same way
+ * whether or not its diacritics are kept.
+ *
+ * ```
+ * use crk_or_eng::{normalize, Normalization};
+ *
+ * let normalization = Normalization::default();
+ * assert_eq!(normalize("nêhiyawêwin", normalization), "nehiyawewin");
+ * assert_eq!(normalize("ne\u{0302}hiyawe\u{0302}win", normalization), "nehiyawewin");
+ * assert_eq!(normalize("nēhiyawēwin", normalization), "nehiyawewin");
+ * assert_eq!(normalize("Tánisi!", normalization), "tanisi");
+ * assert_eq!(normalize("ᓀᐦᐃᔭᐍᐏᐣ", normalization), "nehiyawewin");
+ * assert_eq!(normalize("a\n\u{301}", normalization), "a");
+ *
+ * let normalization = Normalization { keep_vowel_length: true, ..normalization };
+ * assert_eq!(normalize("ne\u{0302}hiyawe\u{0302}win", normalization), "nêhiyawêwin");
+ * assert_eq!(normalize("nēhiyawēwin", normalization), "nêhiyawêwin");
+ * assert_eq!(normalize("Tánisi!", normalization), "tanisi");
+ *
+ * let normalization = Normalization { strip_diacritics: false, ..normalization };
+ * assert_eq!(normalize("Tánisi!", normalization), "tánisi");
+ *
+ * // Capital I with a dot lowercases to i and a combining dot above.
+ * assert_eq!(normalize("İstanbul", normalization), "i\u{0307}stanbul");
+ * assert_eq!(normalize("İstanbul", Normalization::default()), "istanbul");
+ * assert_eq!(normalize("ΟΔΟΣ", normalization), "οδος");
+ * assert_eq!(normalize("STRAẞE", normalization), "straße");
+ *
+ * let normalization = Normalization { case_fold: true, ..normalization };
+ * assert_eq!(normalize("ΟΔΟΣ", normalization), "οδοσ");
+ * assert_eq!(normalize("STRAẞE", normalization), "strasse");
+ * assert_eq!(normalize("Straße", normalization), "strasse");
+ * assert_eq!(normalize("ǅ", normalization), "ǆ");
+ * ```
+ */
+pub fn normalize(line: &str, normalization: Normalization) -> String {
+  let transliterated;
+  let word = if normalization.transliterate_syllabics {
+    transliterated = transliterate(line);
+    &transliterated
+  } else {
+    line
+  };
+
+  let word = if normalization.case_fold {
+    default_case_fold_str(word)
+  } else if normalization.lowercase {
+    word.to_lowercase()
+  } else {
+    word.to_owned()
+  };
+
+  let mut decomposed = String::new();
+  let mut after_vowel = false;
+  for ch in word.nfd() {
+    if !is_combining_mark(ch) {
+      after_vowel = is_sro_vowel(ch);
+    } else if normalization.keep_vowel_length && after_vowel
+      && (ch == COMBINING_CIRCUMFLEX || ch == COMBINING_MACRON) {
+      // A vowel is only so long: any further marks are other diacritics.
+      after_vowel = false;
+      decomposed.push(COMBINING_CIRCUMFLEX);
+      continue;
+    } else if normalization.strip_diacritics {
+      continue;
+    }
+
+    decomposed.push(ch);
+  }
+
+  // Remove extraneous spaces and punctuation, last of all, since stripping
+  // diacritics can leave them at the end.
+  let mut word: String = decomposed.nfc().collect();
+  let length = word.trim_end_matches(|c| "!? \n".contains(c)).len();
+  word.truncate(length);
+  word
+}
+
+
+fn is_sro_vowel(ch: char) -> bool {
+  "aeioAEIO".contains(ch)
+}
+
+
+impl Default for Normalization {
+  fn default() -> Normalization {
+    Normalization {
+      transliterate_syllabics: true,
+      lowercase: true,
+      case_fold: false,
+      strip_diacritics: true,
+      keep_vowel_length: false,
+    }
+  }
+}
@@ -0,0 +1,216 @@
+/*
+ * Copyright (C) 2018 Eddie Antonio Santos <easantos@ualberta.ca>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+
+//! Feature selection, for shrinking a model down to the n-grams that best tell
+//! the languages apart.
+
+use std::fmt;
+use std::str::FromStr;
+
+use classifier::Occurance;
+
+/**
+ * Keep only the best n-grams according to some criterion.
+ */
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub struct Selection {
+  /// How to score each n-gram.
+  pub criterion: Criterion,
+  /// How many of the highest-scoring n-grams to keep.
+  pub keep: usize,
+}
+
+/**
+ * How to score an n-gram for feature selection. Each compares the words of one
+ * language that have the n-gram against those of all the other languages; an
+ * n-gram's score is its best score for any language. (In the multinomial
+ * model, each n-gram counted takes the place of a word, so an n-gram is
+ * compared against all the other n-grams counted in each language.)
+ */
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum Criterion {
+  /// Pearson's chi-square test of independence between the n-gram and the
+  /// language.
+  ChiSquare,
+  /// How much knowing whether a word has the n-gram tells us about its
+  /// language, in nats.
+  MutualInformation,
+  /// The absolute log of the odds ratio between the language and the rest.
+  LogOdds,
+}
+
+/**
+ * How many features were removed by each step of Classifier::prune_features().
+ */
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub struct Pruning {
+  /// How many n-grams there were to begin with.
+  pub before: usize,
+  /// How many were seen fewer than the minimum count.
+  pub below_min_count: usize,
+  /// How many of the remaining n-grams did not score high enough to be selected.
+  pub not_selected: usize,
+}
+
+
+impl Criterion {
+  /**
+   * Scores the n-gram given how many times it was counted in each of the
+   * languages, and how many events (words, or n-grams in the multinomial
+   * model) were counted in each. Higher is better.
+   */
+  pub(crate) fn score(&self, occ: &Occurance, events: &Occurance, num_languages: usize) -> f64 {
+    (0..num_languages)
+      .map(|index| {
+        // A 2x2 contingency table: events with the n-gram or without,
+        // in this language or not.
+        let with_in = occ.of(index);
+        let with_out = occ.total() - with_in;
+        let without_in = (events.of(index) - with_in).max(0.0);
+        let without_out = (events.total() - events.of(index) - with_out).max(0.0);
+        self.score_table(with_in, with_out, without_in, without_out)
+      })
+      .fold(0.0, f64::max)
+  }
+
+  fn score_table(&self, a: f64, b: f64, c: f64, d: f64) -> f64 {
+    let n = a + b + c + d;
+    match *self {
+      Criterion::ChiSquare => {
+        let denominator = (a + b) * (c + d) * (a + c) * (b + d);
+        if denominator <= 0.0 {
+          return 0.0;
+        }
+        n * (a * d - b * c).powi(2) / denominator
+      },
+      Criterion::MutualInformation => {
+        let cells = [(a, a + b, a + c), (b, a + b, b + d), (c, c + d, a + c), (d, c + d, b + d)];
+        cells.iter()
+          .filter(|&&(count, _, _)| count > 0.0)
+          .map(|&(count, row, column)| count / n * (n * count / (row * column)).ln())
+          .sum()
+      },
+      Criterion::LogOdds => ((a + 0.5) * (d + 0.5) / ((b + 0.5) * (c + 0.5))).ln().abs(),
+    }
+  }
+}
+
+
+impl Pruning {
+  /**
+   * How many n-grams are left.
+   */
+  pub fn after(&self) -> usize {
+    self.before - self.below_min_count - self.not_selected
+  }
+}
+
+
+/**
+ * Parses a criterion and how many n-grams to keep, e.g., "chi-square:1000".
+ */
+impl FromStr for Selection {
+  type Err = ();
+
+  fn from_str(s: &str) -> Result<Selection, ()> {
+    let mut parts = s.splitn(2, ':');
+    match (parts.next(), parts.next()) {
+      (Some(criterion), Some(keep)) => Ok(Selection {
+        criterion: criterion.parse()?,
+        keep: keep.parse().map_err(|_| ())?,
+      }),
+      _ => Err(()),
+    }
+  }
+}
+
+
+impl FromStr for Criterion {
+  type Err = ();
+
+  fn from_str(s: &str) -> Result<Criterion, ()> {
+    match s {
+      "chi-square" => Ok(Criterion::ChiSquare),
+      "mutual-information" => Ok(Criterion::MutualInformation),
+      "log-odds" => Ok(Criterion::LogOdds),
+      _ => Err(()),
+    }
+  }
+}
+
+
+impl fmt::Display for Selection {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{}:{}", self.criterion, self.keep)
+  }
+}
+
+
+impl fmt::Display for Criterion {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    f.write_str(match *self {
+      Criterion::ChiSquare => "chi-square",
+      Criterion::MutualInformation => "mutual-information",
+      Criterion::LogOdds => "log-odds",
+    })
+  }
+}
+
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn close(actual: f64, expected: f64) -> bool {
+    (actual - expected).abs() < 1e-9
+  }
+
+  #[test]
+  fn perfectly_associated_table() {
+    // Every word of the language has the n-gram, and no other word does.
+    assert!(close(Criterion::ChiSquare.score_table(10.0, 0.0, 0.0, 10.0), 20.0));
+    assert!(close(Criterion::MutualInformation.score_table(10.0, 0.0, 0.0, 10.0), 2f64.ln()));
+    assert!(close(Criterion::LogOdds.score_table(10.0, 0.0, 0.0, 10.0), (10.5f64 * 10.5 / 0.25).ln()));
+  }
+
+  #[test]
+  fn independent_table() {
+    for criterion in &[Criterion::ChiSquare, Criterion::MutualInformation, Criterion::LogOdds] {
+      assert!(close(criterion.score_table(5.0, 5.0, 5.0, 5.0), 0.0), "{:?}", criterion);
+    }
+  }
+
+  #[test]
+  fn known_chi_square() {
+    // n (ad - bc)^2 / ((a + b)(c + d)(a + c)(b + d)) = 60 * 300^2 / (20 * 40 * 30 * 30)
+    assert!(close(Criterion::ChiSquare.score_table(15.0, 5.0, 15.0, 25.0), 7.5));
+  }
+
+  #[test]
+  fn score_is_the_best_of_one_language_against_the_rest() {
+    let events = Occurance::from_counts(vec![10.0, 10.0, 10.0]);
+    // Only seen in the first language: its table is (10, 0, 0, 20).
+    let occ = Occurance::from_counts(vec![10.0, 0.0, 0.0]);
+    let expected = Criterion::ChiSquare.score_table(10.0, 0.0, 0.0, 20.0);
+    assert!(close(Criterion::ChiSquare.score(&occ, &events, 3), expected));
+    assert!(close(expected, 30.0));
+    // Seen in every word of every language: it tells them nothing apart.
+    let everywhere = Occurance::from_counts(vec![10.0, 10.0, 10.0]);
+    assert!(close(Criterion::ChiSquare.score(&everywhere, &events, 3), 0.0));
+  }
+}
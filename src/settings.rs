@@ -0,0 +1,338 @@
+/*
+ * Copyright (C) 2018 Eddie Antonio Santos <easantos@ualberta.ca>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+use language::Language;
+use normalize::Normalization;
+use selection::Selection;
+use smoothing::Smoothing;
+
+/**
+ * Everything that determines how a classifier is trained.
+ * These are saved along with the model.
+ */
+#[derive(PartialEq, Debug, Clone)]
+pub struct Settings {
+  pub normalization: Normalization,
+  /// How many tokens are in each n-gram. 2 means digraphs.
+  pub order: usize,
+  /// Whether n-grams are counted once per word, or every time they appear.
+  pub event_model: EventModel,
+  /// How the probability of an n-gram in each language is estimated.
+  pub estimation: Estimation,
+  /// How probability is reserved for n-grams rarely or never seen in a language.
+  pub smoothing: Smoothing,
+  /// What to do with n-grams that the model has never seen.
+  pub unseen: Unseen,
+  /// How likely each language is before looking at the word.
+  pub priors: Priors,
+  /// N-grams seen fewer than this many times are pruned, where each time
+  /// counts as much as the word it was seen in (see Corpus and Format).
+  pub min_count: f64,
+  /// If given, only the best n-grams are kept after pruning.
+  pub selection: Option<Selection>,
+}
+
+/**
+ * How the n-grams of a word are counted, both in training and when classifying.
+ */
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum EventModel {
+  /// Each distinct n-gram counts once per word, no matter how often it appears.
+  Presence,
+  /// Each n-gram counts as many times as it appears in the word, so that
+  /// repeated syllables, as in reduplication, count more than once.
+  Multinomial,
+  /// Like Presence, but when classifying, every known n-gram that is *absent*
+  /// from the word counts as evidence too.
+  Bernoulli,
+}
+
+/**
+ * How the probability of an n-gram in each language is estimated from its counts.
+ */
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum Estimation {
+  /// P(n-gram | language): the n-gram's count in a language, divided by the
+  /// number of words (or, in the multinomial model, n-grams) in that language.
+  Likelihood,
+  /// P(language | n-gram): the n-gram's count in a language, divided by its
+  /// count in all languages. This is how the classifier originally worked;
+  /// it's kept to compare accuracy. The Bernoulli model ignores this setting.
+  Legacy,
+}
+
+/**
+ * The prior probability of each language.
+ */
+#[derive(PartialEq, Debug, Clone)]
+pub enum Priors {
+  /// Every language is equally likely.
+  Uniform,
+  /// Languages are as likely as their share of the words counted in training.
+  Corpus,
+  /// Relative weights for each language, e.g., 0.8 for English and 0.2 for
+  /// nêhiyawêwin. Languages without a weight are never chosen.
+  Explicit(HashMap<Language, f64>),
+}
+
+/**
+ * What to do with n-grams that were never seen in training (or were pruned).
+ */
+#[derive(PartialEq, Debug, Clone)]
+pub enum Unseen {
+  /// Ignore them, as if they weren't in the word at all.
+  Skip,
+  /// Back off to the probability of each of the n-gram's characters in the
+  /// language, so that a letter rare in one language (like 'f' in nêhiyawêwin)
+  /// counts against it.
+  Backoff,
+  /// Add a fixed log-probability for each language, e.g., -8 for nêhiyawêwin
+  /// and -4 for English. Languages without a penalty aren't penalized.
+  Penalty(HashMap<Language, f64>),
+}
+
+
+impl Settings {
+  /**
+   * Checks that the settings make sense together. Otherwise, explains why not.
+   */
+  pub fn validate(&self) -> Result<(), String> {
+    if self.order == 0 {
+      return Err("n-grams must have an order of at least 1".into());
+    }
+    if !self.min_count.is_finite() || self.min_count < 0.0 {
+      return Err("the minimum count must not be negative".into());
+    }
+
+    let multinomial_likelihood = self.event_model == EventModel::Multinomial
+      && self.estimation == Estimation::Likelihood;
+    if self.smoothing.needs_multinomial() && !multinomial_likelihood {
+      return Err(format!("{} smoothing requires the multinomial event model and likelihood estimation",
+                         self.smoothing));
+    }
+
+    Ok(())
+  }
+
+  /**
+   * Changes a setting given its name and value as written in saved models and
+   * training manifests, e.g., "order" and "3".
+   */
+  pub fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+    match key {
+      "transliterate-syllabics" => self.normalization.transliterate_syllabics = parse_yes_or_no(key, value)?,
+      "lowercase" => self.normalization.lowercase = parse_yes_or_no(key, value)?,
+      "case-fold" => self.normalization.case_fold = parse_yes_or_no(key, value)?,
+      "strip-diacritics" => self.normalization.strip_diacritics = parse_yes_or_no(key, value)?,
+      "keep-vowel-length" => self.normalization.keep_vowel_length = parse_yes_or_no(key, value)?,
+      "order" => self.order = parse_value(key, value)?,
+      "event-model" => self.event_model = parse_value(key, value)?,
+      "estimation" => self.estimation = parse_value(key, value)?,
+      "smoothing" => self.smoothing = parse_value(key, value)?,
+      "unseen" => self.unseen = parse_value(key, value)?,
+      "priors" => self.priors = parse_value(key, value)?,
+      "min-count" => self.min_count = parse_value(key, value)?,
+      "select" if value == "all" => self.selection = None,
+      "select" => self.selection = Some(parse_value(key, value)?),
+      _ => return Err(format!("unknown setting '{}'", key)),
+    }
+
+    Ok(())
+  }
+}
+
+
+impl Default for Settings {
+  fn default() -> Settings {
+    Settings {
+      normalization: Normalization::default(),
+      order: 2,
+      event_model: EventModel::Presence,
+      estimation: Estimation::Likelihood,
+      smoothing: Smoothing::default(),
+      unseen: Unseen::Backoff,
+      priors: Priors::Corpus,
+      min_count: 2.0,
+      selection: None,
+    }
+  }
+}
+
+
+impl FromStr for EventModel {
+  type Err = ();
+
+  fn from_str(s: &str) -> Result<EventModel, ()> {
+    match s {
+      "presence" => Ok(EventModel::Presence),
+      "multinomial" => Ok(EventModel::Multinomial),
+      "bernoulli" => Ok(EventModel::Bernoulli),
+      _ => Err(()),
+    }
+  }
+}
+
+
+impl FromStr for Estimation {
+  type Err = ();
+
+  fn from_str(s: &str) -> Result<Estimation, ()> {
+    match s {
+      "likelihood" => Ok(Estimation::Likelihood),
+      "legacy" => Ok(Estimation::Legacy),
+      _ => Err(()),
+    }
+  }
+}
+
+
+/**
+ * Parses "uniform", "corpus", or comma-separated weights, e.g., "crk=0.2,eng=0.8".
+ */
+impl FromStr for Priors {
+  type Err = ();
+
+  fn from_str(s: &str) -> Result<Priors, ()> {
+    match s {
+      "uniform" => return Ok(Priors::Uniform),
+      "corpus" => return Ok(Priors::Corpus),
+      _ => (),
+    }
+
+    let weights = parse_weights(s)?;
+    if weights.values().any(|&weight| weight < 0.0) || weights.values().sum::<f64>() <= 0.0 {
+      return Err(());
+    }
+
+    Ok(Priors::Explicit(weights))
+  }
+}
+
+
+/**
+ * Parses "skip", "backoff", or "penalty:" followed by comma-separated
+ * log-probabilities, e.g., "penalty:crk=-8,eng=-4".
+ */
+impl FromStr for Unseen {
+  type Err = ();
+
+  fn from_str(s: &str) -> Result<Unseen, ()> {
+    match s {
+      "skip" => return Ok(Unseen::Skip),
+      "backoff" => return Ok(Unseen::Backoff),
+      _ => (),
+    }
+
+    let penalties = parse_weights(s.strip_prefix("penalty:").ok_or(())?)?;
+    if penalties.values().any(|&penalty| penalty > 0.0) {
+      return Err(());
+    }
+
+    Ok(Unseen::Penalty(penalties))
+  }
+}
+
+
+impl fmt::Display for EventModel {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    f.write_str(match *self {
+      EventModel::Presence => "presence",
+      EventModel::Multinomial => "multinomial",
+      EventModel::Bernoulli => "bernoulli",
+    })
+  }
+}
+
+
+impl fmt::Display for Priors {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      Priors::Uniform => f.write_str("uniform"),
+      Priors::Corpus => f.write_str("corpus"),
+      Priors::Explicit(ref weights) => f.write_str(&format_weights(weights)),
+    }
+  }
+}
+
+
+impl fmt::Display for Unseen {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      Unseen::Skip => f.write_str("skip"),
+      Unseen::Backoff => f.write_str("backoff"),
+      Unseen::Penalty(ref penalties) => write!(f, "penalty:{}", format_weights(penalties)),
+    }
+  }
+}
+
+
+impl fmt::Display for Estimation {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    f.write_str(match *self {
+      Estimation::Likelihood => "likelihood",
+      Estimation::Legacy => "legacy",
+    })
+  }
+}
+
+
+fn parse_value<T: FromStr>(key: &str, value: &str) -> Result<T, String> {
+  value.parse().map_err(|_| format!("invalid {} '{}'", key, value))
+}
+
+pub(crate) fn parse_yes_or_no(key: &str, value: &str) -> Result<bool, String> {
+  match value {
+    "yes" => Ok(true),
+    "no" => Ok(false),
+    _ => Err(format!("expected yes or no for {}, got '{}'", key, value)),
+  }
+}
+
+/**
+ * Parses a number for each language, e.g., "crk=0.2,eng=0.8".
+ */
+fn parse_weights(s: &str) -> Result<HashMap<Language, f64>, ()> {
+  let mut weights = HashMap::new();
+  for pair in s.split(',') {
+    let mut parts = pair.splitn(2, '=');
+    let (language, weight): (Language, f64) = match (parts.next(), parts.next()) {
+      (Some(language), Some(weight)) => (language.parse()?, weight.parse().map_err(|_| ())?),
+      _ => return Err(()),
+    };
+    if !weight.is_finite() {
+      return Err(());
+    }
+    weights.insert(language, weight);
+  }
+
+  Ok(weights)
+}
+
+fn format_weights(weights: &HashMap<Language, f64>) -> String {
+  let mut languages: Vec<_> = weights.keys().collect();
+  languages.sort();
+
+  let pairs: Vec<_> = languages.iter()
+    .map(|&language| format!("{}={}", language, weights[language]))
+    .collect();
+  pairs.join(",")
+}
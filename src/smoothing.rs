@@ -0,0 +1,259 @@
+/*
+ * Copyright (C) 2018 Eddie Antonio Santos <easantos@ualberta.ca>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Smoothing, so that n-grams never seen in one language still get some
+//! probability in that language.
+
+use std::fmt;
+use std::str::FromStr;
+
+/**
+ * How to estimate the probability of an n-gram in a language from its counts.
+ *
+ * All of these apply to the multinomial model. The presence and Bernoulli
+ * models (and legacy estimation) only count whether an n-gram appears in a
+ * word or not, so there, only Lidstone smoothing makes sense, and
+ * Settings::validate() rejects the others.
+ */
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub enum Smoothing {
+  /// Add alpha to every count. Alpha = 1 is Laplace (add-one) smoothing.
+  Lidstone { alpha: f64 },
+  /// Reserve probability for unseen n-grams in proportion to the number of
+  /// distinct n-grams seen in the language.
+  WittenBell,
+  /// Subtract a discount, between 0 and 1, from every count, and share it
+  /// equally among all n-grams.
+  AbsoluteDiscounting { discount: f64 },
+  /// Like absolute discounting, but share the discount according to how many
+  /// different contexts each n-gram's (n - 1)-gram suffix appears in.
+  KneserNey { discount: f64 },
+}
+
+/**
+ * Everything smoothing needs to know about one n-gram in one language.
+ */
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct Counts {
+  /// How many times the n-gram was counted in the language.
+  pub(crate) count: f64,
+  /// How many n-grams, in total, were counted in the language.
+  pub(crate) total: f64,
+  /// How many distinct n-grams were seen in the language.
+  pub(crate) types: f64,
+  /// How many distinct n-grams are known in any language.
+  pub(crate) vocabulary: f64,
+  /// For Kneser-Ney: the probability of the n-gram under the lower-order,
+  /// continuation distribution.
+  pub(crate) continuation: f64,
+  /// For absolute discounting and Kneser-Ney: how much was discounted from
+  /// all of the n-grams counted in the language. Each loses the discount, or
+  /// its whole count if that's smaller (as it can be for weighted words).
+  pub(crate) discounted: f64,
+}
+
+
+impl Smoothing {
+  /**
+   * Estimates P(n-gram | language), such that the estimates for every known
+   * n-gram sum to 1.
+   */
+  pub(crate) fn probability(&self, counts: Counts) -> f64 {
+    let Counts { count, total, types, vocabulary, continuation, discounted } = counts;
+    if total <= 0.0 {
+      // Nothing was counted in this language, so all n-grams are equally likely.
+      return 1.0 / vocabulary;
+    }
+
+    match *self {
+      Smoothing::Lidstone { alpha } => (count + alpha) / (total + alpha * vocabulary),
+      Smoothing::WittenBell if count > 0.0 => count / (total + types),
+      Smoothing::WittenBell => types / ((total + types) * (vocabulary - types)),
+      Smoothing::AbsoluteDiscounting { discount } =>
+        interpolate(count, total, discount, discounted, 1.0 / vocabulary),
+      Smoothing::KneserNey { discount } =>
+        interpolate(count, total, discount, discounted, continuation),
+    }
+  }
+
+  /**
+   * Estimates the probability that a word has an n-gram, given how many of
+   * the words counted had it. Only Lidstone smoothing applies.
+   */
+  pub(crate) fn binary_probability(&self, count: f64, total: f64) -> f64 {
+    let alpha = self.lidstone_alpha();
+    (count + alpha) / (total + 2.0 * alpha)
+  }
+
+  /**
+   * Estimates the probability that an n-gram is in a particular language,
+   * given how many times it was counted in all languages. This is legacy
+   * estimation: Lidstone smoothing, but with the vocabulary size in the
+   * denominator, as the classifier originally worked.
+   */
+  pub(crate) fn legacy_probability(&self, count: f64, total: f64, vocabulary: f64) -> f64 {
+    let alpha = self.lidstone_alpha();
+    (count + alpha) / (total + alpha * vocabulary)
+  }
+
+  /**
+   * The alpha of Lidstone smoothing, in the models where nothing else is
+   * allowed. Panics otherwise, since the settings were never validated.
+   */
+  fn lidstone_alpha(&self) -> f64 {
+    match *self {
+      Smoothing::Lidstone { alpha } => alpha,
+      _ => panic!("{} smoothing requires the multinomial event model and likelihood estimation", self),
+    }
+  }
+
+  /**
+   * For absolute discounting and Kneser-Ney: how much is discounted from an
+   * n-gram with the given count. Nothing, for other smoothing.
+   */
+  pub(crate) fn discount_of(&self, count: f64) -> f64 {
+    match *self {
+      Smoothing::AbsoluteDiscounting { discount } | Smoothing::KneserNey { discount } =>
+        count.min(discount),
+      _ => 0.0,
+    }
+  }
+
+  /**
+   * Whether this only makes sense in the multinomial model.
+   */
+  pub(crate) fn needs_multinomial(&self) -> bool {
+    !matches!(*self, Smoothing::Lidstone { .. })
+  }
+}
+
+
+/**
+ * Interpolates discounted counts with a lower-order distribution, which gets
+ * all of the probability that was discounted.
+ */
+fn interpolate(count: f64, total: f64, discount: f64, discounted: f64, lower_order: f64) -> f64 {
+  let reserved = discounted / total;
+  (count - discount).max(0.0) / total + reserved * lower_order
+}
+
+
+impl Default for Smoothing {
+  fn default() -> Smoothing {
+    Smoothing::Lidstone { alpha: 1.0 }
+  }
+}
+
+
+/**
+ * Parses "lidstone:ALPHA", "witten-bell", "absolute:DISCOUNT", or
+ * "kneser-ney:DISCOUNT". "laplace" is short for "lidstone:1".
+ */
+impl FromStr for Smoothing {
+  type Err = ();
+
+  fn from_str(s: &str) -> Result<Smoothing, ()> {
+    let mut parts = s.splitn(2, ':');
+    let name = parts.next().unwrap_or("");
+    let parameter = match parts.next() {
+      Some(parameter) => Some(parameter.parse::<f64>().map_err(|_| ())?),
+      None => None,
+    };
+
+    let smoothing = match (name, parameter) {
+      ("laplace", None) => Smoothing::Lidstone { alpha: 1.0 },
+      ("lidstone", Some(alpha)) if alpha > 0.0 && alpha.is_finite() => Smoothing::Lidstone { alpha },
+      ("witten-bell", None) => Smoothing::WittenBell,
+      ("absolute", Some(discount)) if discount > 0.0 && discount <= 1.0 =>
+        Smoothing::AbsoluteDiscounting { discount },
+      ("kneser-ney", Some(discount)) if discount > 0.0 && discount <= 1.0 =>
+        Smoothing::KneserNey { discount },
+      _ => return Err(()),
+    };
+
+    Ok(smoothing)
+  }
+}
+
+
+impl fmt::Display for Smoothing {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      Smoothing::Lidstone { alpha } => write!(f, "lidstone:{}", alpha),
+      Smoothing::WittenBell => f.write_str("witten-bell"),
+      Smoothing::AbsoluteDiscounting { discount } => write!(f, "absolute:{}", discount),
+      Smoothing::KneserNey { discount } => write!(f, "kneser-ney:{}", discount),
+    }
+  }
+}
+
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// Counts of five n-grams in one language, two of them never seen.
+  const COUNTS: [f64; 5] = [5.0, 3.0, 1.0, 0.0, 0.0];
+  /// Weighted counts, some smaller than the usual discounts.
+  const FRACTIONAL_COUNTS: [f64; 5] = [0.5, 3.0, 0.693, 0.1, 0.0];
+  /// A lower-order distribution over the same n-grams, for Kneser-Ney.
+  const CONTINUATION: [f64; 5] = [0.4, 0.3, 0.1, 0.1, 0.1];
+
+  fn sum_of_probabilities(smoothing: Smoothing, counts: &[f64]) -> f64 {
+    let total = counts.iter().sum();
+    let types = counts.iter().filter(|&&count| count > 0.0).count() as f64;
+    let discounted = counts.iter().map(|&count| smoothing.discount_of(count)).sum();
+    counts.iter().zip(CONTINUATION.iter())
+      .map(|(&count, &continuation)| smoothing.probability(Counts {
+        count,
+        total,
+        types,
+        vocabulary: counts.len() as f64,
+        continuation,
+        discounted,
+      }))
+      .sum()
+  }
+
+  #[test]
+  fn every_smoothing_sums_to_one() {
+    let smoothings = [
+      Smoothing::Lidstone { alpha: 1.0 },
+      Smoothing::Lidstone { alpha: 0.1 },
+      Smoothing::WittenBell,
+      Smoothing::AbsoluteDiscounting { discount: 0.5 },
+      Smoothing::AbsoluteDiscounting { discount: 1.0 },
+      Smoothing::KneserNey { discount: 0.75 },
+    ];
+
+    for &smoothing in &smoothings {
+      let sum = sum_of_probabilities(smoothing, &COUNTS);
+      assert!((sum - 1.0).abs() < 1e-9, "{} sums to {}", smoothing, sum);
+      let sum = sum_of_probabilities(smoothing, &FRACTIONAL_COUNTS);
+      assert!((sum - 1.0).abs() < 1e-9, "{} sums to {} with fractional counts", smoothing, sum);
+      let sum = sum_of_probabilities(smoothing, &[0.0; 5]);
+      assert!((sum - 1.0).abs() < 1e-9, "{} sums to {} with no counts", smoothing, sum);
+    }
+  }
+
+  #[test]
+  fn lidstone_needs_a_finite_alpha() {
+    assert_eq!("lidstone:0.5".parse(), Ok(Smoothing::Lidstone { alpha: 0.5 }));
+    assert_eq!("lidstone:inf".parse::<Smoothing>(), Err(()));
+    assert_eq!("lidstone:NaN".parse::<Smoothing>(), Err(()));
+  }
+}
@@ -0,0 +1,332 @@
+/*
+ * Copyright (C) 2018 Eddie Antonio Santos <easantos@ualberta.ca>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+
+//! Transliterating Cree syllabics into Standard Roman Orthography (SRO).
+
+/// The dot written after a syllable to add a w, as in ᑲᐧ (kwa).
+const W_DOT: char = '\u{1427}';
+
+/**
+ * Rewrites Plains Cree syllabics in Standard Roman Orthography, leaving
+ * everything else as it is. Syllables (including the w-dot forms) become a
+ * consonant and a vowel, and finals (including the final h) become a
+ * consonant; long vowels are written with circumflexes.
+ *
+ * ```
+ * use crk_or_eng::transliterate;
+ *
+ * assert_eq!(transliterate("ᓀᐦᐃᔭᐍᐏᐣ"), "nêhiyawêwin");
+ * assert_eq!(transliterate("ᑖᓂᓯ, ᐊᐋᐧᓯᐢ!"), "tânisi, awâsis!");
+ * assert_eq!(transliterate("ᒥᐢᑕᐦᐃ ᒥᔰᔨᐦᑕᒼ᙮"), "mistahi miywêyihtam.");
+ * // Finals written in the generic (eastern) forms, too.
+ * assert_eq!(transliterate("ᒥᔅᑕᐦᐃ ᒥᔰᔨᐦᑕᒻ"), "mistahi miywêyihtam");
+ * assert_eq!(transliterate("ᒦᒋᓱᒃ ᐊᔅᑭᐩ"), "mîcisok askiy");
+ * ```
+ */
+pub fn transliterate(text: &str) -> String {
+  let mut buffer = String::with_capacity(text.len());
+  // Where the vowel of the last syllable starts, in case it's followed by a w-dot.
+  let mut last_vowel = None;
+
+  for ch in text.chars() {
+    if ch == W_DOT {
+      match last_vowel.take() {
+        Some(index) => buffer.insert(index, 'w'),
+        None => buffer.push('w'),
+      }
+      continue;
+    }
+
+    last_vowel = None;
+    match lookup(ch) {
+      Some(sro) => {
+        if !sro.contains('w') {
+          last_vowel = sro.find(is_vowel).map(|offset| buffer.len() + offset);
+        }
+        buffer.push_str(sro);
+      },
+      None => buffer.push(ch),
+    }
+  }
+
+  buffer
+}
+
+
+fn lookup(ch: char) -> Option<&'static str> {
+  SYLLABICS.binary_search_by_key(&ch, |&(syllabic, _)| syllabic)
+    .ok()
+    .map(|index| SYLLABICS[index].1)
+}
+
+fn is_vowel(ch: char) -> bool {
+  "aeioâêîô".contains(ch)
+}
+
+/**
+ * Each syllabic and how it is written in SRO, sorted by code point.
+ * Generated from the names in the Unicode Character Database: the plain and
+ * West Cree syllables of the p, t, k, c, m, n, s, y, l, r and w series, and
+ * the finals used in Plains Cree (both West Cree and generic forms).
+ */
+const SYLLABICS: &[(char, &str)] = &[
+  ('\u{1400}', "-"), // ᐀ HYPHEN
+  ('\u{1401}', "ê"), // ᐁ E
+  ('\u{1403}', "i"), // ᐃ I
+  ('\u{1404}', "î"), // ᐄ II
+  ('\u{1405}', "o"), // ᐅ O
+  ('\u{1406}', "ô"), // ᐆ OO
+  ('\u{140A}', "a"), // ᐊ A
+  ('\u{140B}', "â"), // ᐋ AA
+  ('\u{140C}', "wê"), // ᐌ WE
+  ('\u{140D}', "wê"), // ᐍ WEST-CREE WE
+  ('\u{140E}', "wi"), // ᐎ WI
+  ('\u{140F}', "wi"), // ᐏ WEST-CREE WI
+  ('\u{1410}', "wî"), // ᐐ WII
+  ('\u{1411}', "wî"), // ᐑ WEST-CREE WII
+  ('\u{1412}', "wo"), // ᐒ WO
+  ('\u{1413}', "wo"), // ᐓ WEST-CREE WO
+  ('\u{1414}', "wô"), // ᐔ WOO
+  ('\u{1415}', "wô"), // ᐕ WEST-CREE WOO
+  ('\u{1417}', "wa"), // ᐗ WA
+  ('\u{1418}', "wa"), // ᐘ WEST-CREE WA
+  ('\u{1419}', "wâ"), // ᐙ WAA
+  ('\u{141A}', "wâ"), // ᐚ WEST-CREE WAA
+  ('\u{141F}', "t"), // ᐟ FINAL ACUTE
+  ('\u{1420}', "k"), // ᐠ FINAL GRAVE
+  ('\u{1422}', "s"), // ᐢ FINAL TOP HALF RING
+  ('\u{1423}', "n"), // ᐣ FINAL RIGHT HALF RING
+  ('\u{1424}', "w"), // ᐤ FINAL RING
+  ('\u{1426}', "h"), // ᐦ FINAL DOUBLE SHORT VERTICAL STROKES
+  ('\u{1428}', "c"), // ᐨ FINAL SHORT HORIZONTAL STROKE
+  ('\u{1429}', "y"), // ᐩ FINAL PLUS
+  ('\u{142F}', "pê"), // ᐯ PE
+  ('\u{1431}', "pi"), // ᐱ PI
+  ('\u{1432}', "pî"), // ᐲ PII
+  ('\u{1433}', "po"), // ᐳ PO
+  ('\u{1434}', "pô"), // ᐴ POO
+  ('\u{1438}', "pa"), // ᐸ PA
+  ('\u{1439}', "pâ"), // ᐹ PAA
+  ('\u{143A}', "pwê"), // ᐺ PWE
+  ('\u{143B}', "pwê"), // ᐻ WEST-CREE PWE
+  ('\u{143C}', "pwi"), // ᐼ PWI
+  ('\u{143D}', "pwi"), // ᐽ WEST-CREE PWI
+  ('\u{143E}', "pwî"), // ᐾ PWII
+  ('\u{143F}', "pwî"), // ᐿ WEST-CREE PWII
+  ('\u{1440}', "pwo"), // ᑀ PWO
+  ('\u{1441}', "pwo"), // ᑁ WEST-CREE PWO
+  ('\u{1442}', "pwô"), // ᑂ PWOO
+  ('\u{1443}', "pwô"), // ᑃ WEST-CREE PWOO
+  ('\u{1444}', "pwa"), // ᑄ PWA
+  ('\u{1445}', "pwa"), // ᑅ WEST-CREE PWA
+  ('\u{1446}', "pwâ"), // ᑆ PWAA
+  ('\u{1447}', "pwâ"), // ᑇ WEST-CREE PWAA
+  ('\u{1449}', "p"), // ᑉ P
+  ('\u{144A}', "p"), // ᑊ WEST-CREE P
+  ('\u{144C}', "tê"), // ᑌ TE
+  ('\u{144E}', "ti"), // ᑎ TI
+  ('\u{144F}', "tî"), // ᑏ TII
+  ('\u{1450}', "to"), // ᑐ TO
+  ('\u{1451}', "tô"), // ᑑ TOO
+  ('\u{1455}', "ta"), // ᑕ TA
+  ('\u{1456}', "tâ"), // ᑖ TAA
+  ('\u{1457}', "twê"), // ᑗ TWE
+  ('\u{1458}', "twê"), // ᑘ WEST-CREE TWE
+  ('\u{1459}', "twi"), // ᑙ TWI
+  ('\u{145A}', "twi"), // ᑚ WEST-CREE TWI
+  ('\u{145B}', "twî"), // ᑛ TWII
+  ('\u{145C}', "twî"), // ᑜ WEST-CREE TWII
+  ('\u{145D}', "two"), // ᑝ TWO
+  ('\u{145E}', "two"), // ᑞ WEST-CREE TWO
+  ('\u{145F}', "twô"), // ᑟ TWOO
+  ('\u{1460}', "twô"), // ᑠ WEST-CREE TWOO
+  ('\u{1461}', "twa"), // ᑡ TWA
+  ('\u{1462}', "twa"), // ᑢ WEST-CREE TWA
+  ('\u{1463}', "twâ"), // ᑣ TWAA
+  ('\u{1464}', "twâ"), // ᑤ WEST-CREE TWAA
+  ('\u{1466}', "t"), // ᑦ T
+  ('\u{146B}', "kê"), // ᑫ KE
+  ('\u{146D}', "ki"), // ᑭ KI
+  ('\u{146E}', "kî"), // ᑮ KII
+  ('\u{146F}', "ko"), // ᑯ KO
+  ('\u{1470}', "kô"), // ᑰ KOO
+  ('\u{1472}', "ka"), // ᑲ KA
+  ('\u{1473}', "kâ"), // ᑳ KAA
+  ('\u{1474}', "kwê"), // ᑴ KWE
+  ('\u{1475}', "kwê"), // ᑵ WEST-CREE KWE
+  ('\u{1476}', "kwi"), // ᑶ KWI
+  ('\u{1477}', "kwi"), // ᑷ WEST-CREE KWI
+  ('\u{1478}', "kwî"), // ᑸ KWII
+  ('\u{1479}', "kwî"), // ᑹ WEST-CREE KWII
+  ('\u{147A}', "kwo"), // ᑺ KWO
+  ('\u{147B}', "kwo"), // ᑻ WEST-CREE KWO
+  ('\u{147C}', "kwô"), // ᑼ KWOO
+  ('\u{147D}', "kwô"), // ᑽ WEST-CREE KWOO
+  ('\u{147E}', "kwa"), // ᑾ KWA
+  ('\u{147F}', "kwa"), // ᑿ WEST-CREE KWA
+  ('\u{1480}', "kwâ"), // ᒀ KWAA
+  ('\u{1481}', "kwâ"), // ᒁ WEST-CREE KWAA
+  ('\u{1483}', "k"), // ᒃ K
+  ('\u{1489}', "cê"), // ᒉ CE
+  ('\u{148B}', "ci"), // ᒋ CI
+  ('\u{148C}', "cî"), // ᒌ CII
+  ('\u{148D}', "co"), // ᒍ CO
+  ('\u{148E}', "cô"), // ᒎ COO
+  ('\u{1490}', "ca"), // ᒐ CA
+  ('\u{1491}', "câ"), // ᒑ CAA
+  ('\u{1492}', "cwê"), // ᒒ CWE
+  ('\u{1493}', "cwê"), // ᒓ WEST-CREE CWE
+  ('\u{1494}', "cwi"), // ᒔ CWI
+  ('\u{1495}', "cwi"), // ᒕ WEST-CREE CWI
+  ('\u{1496}', "cwî"), // ᒖ CWII
+  ('\u{1497}', "cwî"), // ᒗ WEST-CREE CWII
+  ('\u{1498}', "cwo"), // ᒘ CWO
+  ('\u{1499}', "cwo"), // ᒙ WEST-CREE CWO
+  ('\u{149A}', "cwô"), // ᒚ CWOO
+  ('\u{149B}', "cwô"), // ᒛ WEST-CREE CWOO
+  ('\u{149C}', "cwa"), // ᒜ CWA
+  ('\u{149D}', "cwa"), // ᒝ WEST-CREE CWA
+  ('\u{149E}', "cwâ"), // ᒞ CWAA
+  ('\u{149F}', "cwâ"), // ᒟ WEST-CREE CWAA
+  ('\u{14A1}', "c"), // ᒡ C
+  ('\u{14A3}', "mê"), // ᒣ ME
+  ('\u{14A5}', "mi"), // ᒥ MI
+  ('\u{14A6}', "mî"), // ᒦ MII
+  ('\u{14A7}', "mo"), // ᒧ MO
+  ('\u{14A8}', "mô"), // ᒨ MOO
+  ('\u{14AA}', "ma"), // ᒪ MA
+  ('\u{14AB}', "mâ"), // ᒫ MAA
+  ('\u{14AC}', "mwê"), // ᒬ MWE
+  ('\u{14AD}', "mwê"), // ᒭ WEST-CREE MWE
+  ('\u{14AE}', "mwi"), // ᒮ MWI
+  ('\u{14AF}', "mwi"), // ᒯ WEST-CREE MWI
+  ('\u{14B0}', "mwî"), // ᒰ MWII
+  ('\u{14B1}', "mwî"), // ᒱ WEST-CREE MWII
+  ('\u{14B2}', "mwo"), // ᒲ MWO
+  ('\u{14B3}', "mwo"), // ᒳ WEST-CREE MWO
+  ('\u{14B4}', "mwô"), // ᒴ MWOO
+  ('\u{14B5}', "mwô"), // ᒵ WEST-CREE MWOO
+  ('\u{14B6}', "mwa"), // ᒶ MWA
+  ('\u{14B7}', "mwa"), // ᒷ WEST-CREE MWA
+  ('\u{14B8}', "mwâ"), // ᒸ MWAA
+  ('\u{14B9}', "mwâ"), // ᒹ WEST-CREE MWAA
+  ('\u{14BB}', "m"), // ᒻ M
+  ('\u{14BC}', "m"), // ᒼ WEST-CREE M
+  ('\u{14C0}', "nê"), // ᓀ NE
+  ('\u{14C2}', "ni"), // ᓂ NI
+  ('\u{14C3}', "nî"), // ᓃ NII
+  ('\u{14C4}', "no"), // ᓄ NO
+  ('\u{14C5}', "nô"), // ᓅ NOO
+  ('\u{14C7}', "na"), // ᓇ NA
+  ('\u{14C8}', "nâ"), // ᓈ NAA
+  ('\u{14C9}', "nwê"), // ᓉ NWE
+  ('\u{14CA}', "nwê"), // ᓊ WEST-CREE NWE
+  ('\u{14CB}', "nwa"), // ᓋ NWA
+  ('\u{14CC}', "nwa"), // ᓌ WEST-CREE NWA
+  ('\u{14CD}', "nwâ"), // ᓍ NWAA
+  ('\u{14CE}', "nwâ"), // ᓎ WEST-CREE NWAA
+  ('\u{14D0}', "n"), // ᓐ N
+  ('\u{14D3}', "lê"), // ᓓ LE
+  ('\u{14D5}', "li"), // ᓕ LI
+  ('\u{14D6}', "lî"), // ᓖ LII
+  ('\u{14D7}', "lo"), // ᓗ LO
+  ('\u{14D8}', "lô"), // ᓘ LOO
+  ('\u{14DA}', "la"), // ᓚ LA
+  ('\u{14DB}', "lâ"), // ᓛ LAA
+  ('\u{14DC}', "lwê"), // ᓜ LWE
+  ('\u{14DD}', "lwê"), // ᓝ WEST-CREE LWE
+  ('\u{14DE}', "lwi"), // ᓞ LWI
+  ('\u{14DF}', "lwi"), // ᓟ WEST-CREE LWI
+  ('\u{14E0}', "lwî"), // ᓠ LWII
+  ('\u{14E1}', "lwî"), // ᓡ WEST-CREE LWII
+  ('\u{14E2}', "lwo"), // ᓢ LWO
+  ('\u{14E3}', "lwo"), // ᓣ WEST-CREE LWO
+  ('\u{14E4}', "lwô"), // ᓤ LWOO
+  ('\u{14E5}', "lwô"), // ᓥ WEST-CREE LWOO
+  ('\u{14E6}', "lwa"), // ᓦ LWA
+  ('\u{14E7}', "lwa"), // ᓧ WEST-CREE LWA
+  ('\u{14E8}', "lwâ"), // ᓨ LWAA
+  ('\u{14E9}', "lwâ"), // ᓩ WEST-CREE LWAA
+  ('\u{14EA}', "l"), // ᓪ L
+  ('\u{14EB}', "l"), // ᓫ WEST-CREE L
+  ('\u{14ED}', "sê"), // ᓭ SE
+  ('\u{14EF}', "si"), // ᓯ SI
+  ('\u{14F0}', "sî"), // ᓰ SII
+  ('\u{14F1}', "so"), // ᓱ SO
+  ('\u{14F2}', "sô"), // ᓲ SOO
+  ('\u{14F4}', "sa"), // ᓴ SA
+  ('\u{14F5}', "sâ"), // ᓵ SAA
+  ('\u{14F6}', "swê"), // ᓶ SWE
+  ('\u{14F7}', "swê"), // ᓷ WEST-CREE SWE
+  ('\u{14F8}', "swi"), // ᓸ SWI
+  ('\u{14F9}', "swi"), // ᓹ WEST-CREE SWI
+  ('\u{14FA}', "swî"), // ᓺ SWII
+  ('\u{14FB}', "swî"), // ᓻ WEST-CREE SWII
+  ('\u{14FC}', "swo"), // ᓼ SWO
+  ('\u{14FD}', "swo"), // ᓽ WEST-CREE SWO
+  ('\u{14FE}', "swô"), // ᓾ SWOO
+  ('\u{14FF}', "swô"), // ᓿ WEST-CREE SWOO
+  ('\u{1500}', "swa"), // ᔀ SWA
+  ('\u{1501}', "swa"), // ᔁ WEST-CREE SWA
+  ('\u{1502}', "swâ"), // ᔂ SWAA
+  ('\u{1503}', "swâ"), // ᔃ WEST-CREE SWAA
+  ('\u{1505}', "s"), // ᔅ S
+  ('\u{1526}', "yê"), // ᔦ YE
+  ('\u{1528}', "yi"), // ᔨ YI
+  ('\u{1529}', "yî"), // ᔩ YII
+  ('\u{152A}', "yo"), // ᔪ YO
+  ('\u{152B}', "yô"), // ᔫ YOO
+  ('\u{152D}', "ya"), // ᔭ YA
+  ('\u{152E}', "yâ"), // ᔮ YAA
+  ('\u{152F}', "ywê"), // ᔯ YWE
+  ('\u{1530}', "ywê"), // ᔰ WEST-CREE YWE
+  ('\u{1531}', "ywi"), // ᔱ YWI
+  ('\u{1532}', "ywi"), // ᔲ WEST-CREE YWI
+  ('\u{1533}', "ywî"), // ᔳ YWII
+  ('\u{1534}', "ywî"), // ᔴ WEST-CREE YWII
+  ('\u{1535}', "ywo"), // ᔵ YWO
+  ('\u{1536}', "ywo"), // ᔶ WEST-CREE YWO
+  ('\u{1537}', "ywô"), // ᔷ YWOO
+  ('\u{1538}', "ywô"), // ᔸ WEST-CREE YWOO
+  ('\u{1539}', "ywa"), // ᔹ YWA
+  ('\u{153A}', "ywa"), // ᔺ WEST-CREE YWA
+  ('\u{153B}', "ywâ"), // ᔻ YWAA
+  ('\u{153C}', "ywâ"), // ᔼ WEST-CREE YWAA
+  ('\u{153E}', "y"), // ᔾ Y
+  ('\u{1540}', "y"), // ᕀ WEST-CREE Y
+  ('\u{1542}', "rê"), // ᕂ RE
+  ('\u{1544}', "lê"), // ᕄ WEST-CREE LE
+  ('\u{1546}', "ri"), // ᕆ RI
+  ('\u{1547}', "rî"), // ᕇ RII
+  ('\u{1548}', "ro"), // ᕈ RO
+  ('\u{1549}', "rô"), // ᕉ ROO
+  ('\u{154A}', "lo"), // ᕊ WEST-CREE LO
+  ('\u{154B}', "ra"), // ᕋ RA
+  ('\u{154C}', "râ"), // ᕌ RAA
+  ('\u{154D}', "la"), // ᕍ WEST-CREE LA
+  ('\u{154E}', "rwâ"), // ᕎ RWAA
+  ('\u{154F}', "rwâ"), // ᕏ WEST-CREE RWAA
+  ('\u{1550}', "r"), // ᕐ R
+  ('\u{1551}', "r"), // ᕑ WEST-CREE R
+  ('\u{157D}', "hk"), // ᕽ HK
+  ('\u{158A}', "rê"), // ᖊ WEST-CREE RE
+  ('\u{158B}', "ri"), // ᖋ WEST-CREE RI
+  ('\u{158C}', "ro"), // ᖌ WEST-CREE RO
+  ('\u{158D}', "ra"), // ᖍ WEST-CREE RA
+  ('\u{166E}', "."), // ᙮ FULL STOP
+];
@@ -0,0 +1,195 @@
+/*
+ * Copyright (C) 2018 Eddie Antonio Santos <easantos@ualberta.ca>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+
+//! Tagging each word of code-switched text with its language.
+
+use classifier::{Classifier, Reason, Verdict};
+use language::Language;
+
+/// The default cost, in nats, of switching languages between two words.
+pub const DEFAULT_SWITCH_PENALTY: f64 = 3.0;
+
+/**
+ * A run of consecutive words in the same language.
+ */
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub struct Span {
+  pub language: Language,
+  /// The index of the first word of the span.
+  pub start: usize,
+  /// The index just past the last word of the span.
+  pub end: usize,
+}
+
+
+impl Classifier {
+  /**
+   * Finds the most likely language of each word, given that languages tend
+   * not to switch from one word to the next, and returns them as spans.
+   *
+   * This is the Viterbi algorithm over a hidden Markov model whose states are
+   * the languages: the first word starts with the languages' priors, each
+   * word contributes its log-likelihood in each language, and every switch
+   * between languages costs `switch_penalty` (a log-probability, so 0 means
+   * each word is classified independently, and larger numbers make short,
+   * ambiguous words like "a" or "ok" take the language of their neighbours).
+   *
+   * Panics if the penalty is negative (which would reward switching) or not
+   * a finite number.
+   */
+  pub fn tag(&self, words: &[&str], switch_penalty: f64) -> Vec<Span> {
+    assert!(switch_penalty.is_finite() && switch_penalty >= 0.0,
+            "switch penalty must be a finite number, at least 0; got {}", switch_penalty);
+    let num_languages = self.languages.len();
+    if words.is_empty() || num_languages == 0 {
+      return Vec::new();
+    }
+
+    // For each word, the log-likelihood in each language, in the same order
+    // as self.languages. Words with no evidence are equally likely in every
+    // language, so that they take the language of their neighbours.
+    let emissions: Vec<Vec<f64>> = words.iter().map(|word| {
+      let mut log_likelihoods = vec![0.0; num_languages];
+      let result = self.classify(word);
+      if result.verdict != Verdict::Unknown(Reason::NoEvidence) {
+        for score in result.scores {
+          log_likelihoods[self.position_of(score.language)] = score.log_likelihood;
+        }
+      }
+      log_likelihoods
+    }).collect();
+
+    // The log-probability of the best path ending in each language.
+    let mut best: Vec<f64> = emissions[0].iter().enumerate()
+      .map(|(index, log_likelihood)| self.log_prior(index) + log_likelihood)
+      .collect();
+    // For each word after the first, the language of the previous word on the
+    // best path that ends in each language.
+    let mut backpointers: Vec<Vec<usize>> = Vec::with_capacity(words.len() - 1);
+
+    for emission in &emissions[1..] {
+      let mut next = Vec::with_capacity(num_languages);
+      let mut pointers = Vec::with_capacity(num_languages);
+      for (to, log_likelihood) in emission.iter().enumerate() {
+        let arriving = |from: usize| best[from] - if from == to { 0.0 } else { switch_penalty };
+        // Staying in the same language wins ties.
+        let from = (0..num_languages)
+          .fold(to, |from, candidate| if arriving(candidate) > arriving(from) { candidate } else { from });
+        next.push(arriving(from) + log_likelihood);
+        pointers.push(from);
+      }
+      best = next;
+      backpointers.push(pointers);
+    }
+
+    // Follow the backpointers from the best final language.
+    let mut state = (0..num_languages)
+      .fold(0, |argmax, index| if best[index] > best[argmax] { index } else { argmax });
+    let mut path = vec![state];
+    for pointers in backpointers.iter().rev() {
+      state = pointers[state];
+      path.push(state);
+    }
+    path.reverse();
+
+    let mut spans: Vec<Span> = Vec::new();
+    for (position, &index) in path.iter().enumerate() {
+      let language = self.languages[index];
+      match spans.last_mut() {
+        Some(span) if span.language == language => span.end = position + 1,
+        _ => spans.push(Span { language, start: position, end: position + 1 }),
+      }
+    }
+
+    spans
+  }
+}
+
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use settings::{Priors, Settings};
+
+  fn span(language: Language, start: usize, end: usize) -> Span {
+    Span { language, start, end }
+  }
+
+  /// Trained on a few words of each language, with uniform priors.
+  fn classifier() -> Classifier {
+    let settings = Settings { priors: Priors::Uniform, ..Settings::default() };
+    let mut classifier = Classifier::with_settings(settings);
+    for word in &["maskwa", "nipiy", "awâsis", "nêhiyawêwin", "wâpos", "sâkahikan"] {
+      classifier.count_ngrams_in_word(word, Language::CRK);
+    }
+    for word in &["the", "bear", "water", "child", "rabbit", "lake", "a", "an"] {
+      classifier.count_ngrams_in_word(word, Language::ENG);
+    }
+    classifier
+  }
+
+  #[test]
+  fn single_word() {
+    let classifier = classifier();
+    assert_eq!(classifier.tag(&["maskwa"], DEFAULT_SWITCH_PENALTY), vec![span(Language::CRK, 0, 1)]);
+    assert_eq!(classifier.tag(&["the"], DEFAULT_SWITCH_PENALTY), vec![span(Language::ENG, 0, 1)]);
+    assert_eq!(classifier.tag(&[], DEFAULT_SWITCH_PENALTY), vec![]);
+  }
+
+  #[test]
+  fn ties_stay_in_the_same_language() {
+    // Every word is just as likely in either language.
+    let mut classifier = Classifier::with_settings(Settings { priors: Priors::Uniform, ..Settings::default() });
+    classifier.count_ngrams_in_word("mama", Language::CRK);
+    classifier.count_ngrams_in_word("mama", Language::ENG);
+
+    let spans = classifier.tag(&["mama", "mama", "mama"], 0.0);
+    assert_eq!(spans.len(), 1);
+    assert_eq!((spans[0].start, spans[0].end), (0, 3));
+  }
+
+  #[test]
+  fn words_with_no_evidence_take_their_neighbours_language() {
+    let classifier = classifier();
+    assert_eq!(classifier.tag(&["maskwa", "中文", "中文", "中文", "nipiy"], 0.0),
+               vec![span(Language::CRK, 0, 5)]);
+    assert_eq!(classifier.tag(&["中文", "the", "bear"], 0.0), vec![span(Language::ENG, 0, 3)]);
+  }
+
+  #[test]
+  fn ambiguous_word_between_two_spans() {
+    let classifier = classifier();
+    // On its own, "war" is (barely) English, and "a" (barely) Cree...
+    assert_eq!(classifier.classify("war").language(), Some(Language::ENG));
+    assert_eq!(classifier.classify("a").language(), Some(Language::CRK));
+
+    // ...so without a penalty, each is a span of its own...
+    let cree = ["maskwa", "nipiy", "war", "awâsis", "wâpos"];
+    assert_eq!(classifier.tag(&cree, 0.0), vec![
+      span(Language::CRK, 0, 2),
+      span(Language::ENG, 2, 3),
+      span(Language::CRK, 3, 5),
+    ]);
+    // ...but with one, it takes the language of the words around it.
+    assert_eq!(classifier.tag(&cree, DEFAULT_SWITCH_PENALTY), vec![span(Language::CRK, 0, 5)]);
+
+    let english = ["the", "bear", "a", "water", "lake"];
+    assert_eq!(classifier.tag(&english, 0.0).len(), 3);
+    assert_eq!(classifier.tag(&english, DEFAULT_SWITCH_PENALTY), vec![span(Language::ENG, 0, 5)]);
+  }
+}
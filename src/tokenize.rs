@@ -0,0 +1,105 @@
+/*
+ * Copyright (C) 2018 Eddie Antonio Santos <easantos@ualberta.ca>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+
+//! Splitting running text into words.
+
+use std::str::CharIndices;
+
+use unicode_normalization::char::is_combining_mark;
+
+/**
+ * Splits running text into the words worth classifying.
+ *
+ * A word is a run of letters (and combining marks). Hyphens and apostrophes
+ * are kept between letters, so that Cree preverbs stay with their verbs and
+ * English contractions stay whole; all other punctuation, including quotes,
+ * separates words. Numbers, and words with digits in them, are skipped.
+ *
+ * ```
+ * use crk_or_eng::words;
+ *
+ * let text = "Tânisi, kiya? “ê-nipât” isiyihkâsow ka-wâpamat 2 don't-know.";
+ * assert_eq!(words(text).collect::<Vec<_>>(),
+ *            ["Tânisi", "kiya", "ê-nipât", "isiyihkâsow", "ka-wâpamat", "don't-know"]);
+ * ```
+ */
+pub fn words<'a>(text: &'a str) -> Words<'a> {
+  Words { text, chars: text.char_indices() }
+}
+
+/**
+ * An iterator over the words in some text; see words().
+ */
+#[derive(Debug, Clone)]
+pub struct Words<'a> {
+  text: &'a str,
+  chars: CharIndices<'a>,
+}
+
+
+impl<'a> Iterator for Words<'a> {
+  type Item = &'a str;
+
+  fn next(&mut self) -> Option<&'a str> {
+    loop {
+      // Skip to the start of the next word.
+      let start = loop {
+        match self.chars.next() {
+          Some((index, c)) if is_word_char(c) => break index,
+          Some(_) => (),
+          None => return None,
+        }
+      };
+
+      // Then find its end: a joiner only continues the word if a letter
+      // follows it.
+      let mut end = self.text.len();
+      let mut lookahead = self.chars.clone();
+      while let Some((index, c)) = lookahead.next() {
+        let continues = is_word_char(c)
+          || (is_joiner(c) && lookahead.clone().next().is_some_and(|(_, next)| is_word_char(next)));
+        if !continues {
+          end = index;
+          break;
+        }
+        self.chars.next();
+      }
+
+      let word = &self.text[start..end];
+      if !word.chars().any(|c| c.is_numeric()) {
+        return Some(word);
+      }
+    }
+  }
+}
+
+
+/**
+ * Letters, digits (so that numbers are skipped whole), and combining marks.
+ */
+fn is_word_char(c: char) -> bool {
+  c.is_alphanumeric() || is_combining_mark(c)
+}
+
+/**
+ * Hyphens (including the syllabics hyphen) and apostrophes, which may join
+ * the parts of a word.
+ */
+fn is_joiner(c: char) -> bool {
+  matches!(c, '-' | '\u{2010}' | '\u{2011}' | '\u{1400}' | '\'' | '\u{2019}' | '\u{02BC}')
+}